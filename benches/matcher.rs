@@ -0,0 +1,107 @@
+// compares the keyword matcher against looking for each keyword in turn, as the crawler did
+// before; run with `cargo bench --bench matcher`
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use hust_news_entry::{Config, Matcher, SuccessTarget};
+
+const TITLES: usize = 1000;
+const CHARS: &str =
+    "华中工学院理科技大同济医武汉北京上海人民日报社会主义建设生产农业新闻研究所厂矿委员";
+
+// the same pseudo random sequence on every run
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, below: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize % below
+    }
+
+    fn text(&mut self, length: usize) -> String {
+        let chars: Vec<char> = CHARS.chars().collect();
+        (0..length).map(|_| chars[self.next(chars.len())]).collect()
+    }
+}
+
+fn keywords(count: usize) -> Vec<String> {
+    let mut random = Random(7);
+    (0..count)
+        .map(|_| {
+            let length = 4 + random.next(4);
+            random.text(length)
+        })
+        .collect()
+}
+
+// titles of 20 characters, a tenth of them containing a keyword
+fn titles(keywords: &[String]) -> Vec<SuccessTarget> {
+    let mut random = Random(11);
+    (0..TITLES)
+        .map(|index| {
+            let mut title = random.text(20);
+            if index % 10 == 0 {
+                title.insert_str(title.len() / 2, &keywords[random.next(keywords.len())]);
+            }
+            SuccessTarget {
+                title,
+                url: String::new(),
+                page: None,
+                page_number: None,
+                keyword: None,
+                entity: None,
+                confidence: None,
+                category: None,
+                row: None,
+            }
+        })
+        .collect()
+}
+
+// the matching step before the multi-pattern matcher, as it was: the title is stripped of
+// whitespace again for every keyword
+fn each_keyword<'a>(keywords: &'a [String], target: &SuccessTarget) -> Option<&'a String> {
+    keywords.iter().find(|each| {
+        target
+            .title
+            .replace(['\n', '\t', '\r', ' '], "")
+            .contains(each.as_str())
+    })
+}
+
+fn matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("match 1000 titles");
+    for count in [5, 100, 1000, 5000] {
+        let keywords = keywords(count);
+        let titles = titles(&keywords);
+        let matcher = Matcher::new(&Config {
+            keywords: keywords.clone(),
+            ..Config::default()
+        });
+        group.bench_with_input(
+            BenchmarkId::new("each keyword", count),
+            &titles,
+            |b, titles| {
+                b.iter(|| {
+                    titles
+                        .iter()
+                        .filter(|title| each_keyword(&keywords, title).is_some())
+                        .count()
+                })
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("matcher", count), &titles, |b, titles| {
+            b.iter(|| {
+                titles
+                    .iter()
+                    .filter(|title| matcher.find(title, None).is_some())
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, matching);
+criterion_main!(benches);
@@ -0,0 +1,13 @@
+#!/bin/sh
+# fetch the pages the parser fixtures should be cut from, as raw-*.html next to this script;
+# trim each to its head, heading and first few items by hand, keeping the served markup,
+# save it over the fixture of the same level and update the expectations of its test
+set -eu
+site=https://cn.govopendata.com/renminribao
+cd "$(dirname "$0")"
+curl -fsS "$site/" -o raw-index.html
+curl -fsS "$site/1974/" -o raw-year.html
+curl -fsS "$site/1974/11/" -o raw-month.html
+curl -fsS "$site/1959/12/5/" -o raw-day.html
+curl -fsS "$site/1959/12/5/4/" -o raw-page.html
+curl -fsS "$site/1956/8/26/7/" -o raw-page_untitled.html
@@ -0,0 +1,440 @@
+use crate::LogExpect;
+use log::{info, warn};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+
+// rates outside these bounds, in requests per second, are clamped to them
+const MIN_RATE: f64 = 0.001;
+const MAX_RATE: f64 = 1000.0;
+// the longest command line read, and how long a connection may stay silent
+const COMMAND_LIMIT: u64 = 1024;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlState {
+    pub paused: bool,
+    // requests allowed in flight at once
+    pub concurrency: usize,
+    pub running: usize,
+    // requests started per second; None is unlimited
+    pub rate: Option<f64>,
+}
+
+// runtime controls of a crawl, shared by the fetcher and whoever steers it
+pub struct Control {
+    state: Mutex<ControlState>,
+    // one permit per request allowed in flight, granted first come first served
+    slots: Arc<Semaphore>,
+    // permits to take out of circulation as they are handed back, after lowering concurrency
+    surplus: Mutex<usize>,
+    // whether requests may start; only those holding a slot wait on it
+    paused: watch::Sender<bool>,
+    // earliest time the next request may start under the rate limit
+    next_request: Mutex<Instant>,
+}
+
+// a line sent to the control socket, e.g. "pause", "concurrency 8" or "rate off"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Pause,
+    Resume,
+    Concurrency(usize),
+    Rate(Option<f64>),
+    Status,
+}
+
+// a request slot, handed back when dropped
+pub struct Permit {
+    control: Arc<Control>,
+    slot: Option<OwnedSemaphorePermit>,
+    // counted as running, once past any pause
+    running: bool,
+}
+
+impl Control {
+    pub fn new(concurrency: usize) -> Arc<Self> {
+        let concurrency = concurrency.max(1);
+        let (paused, _) = watch::channel(false);
+        Arc::new(Control {
+            state: Mutex::new(ControlState {
+                paused: false,
+                concurrency,
+                running: 0,
+                rate: None,
+            }),
+            slots: Arc::new(Semaphore::new(concurrency)),
+            surplus: Mutex::new(0),
+            paused,
+            next_request: Mutex::new(Instant::now()),
+        })
+    }
+
+    pub fn state(&self) -> ControlState {
+        *self.state.lock().log_expect("Failed to aquire lock.")
+    }
+
+    fn modify(&self, change: impl FnOnce(&mut ControlState)) {
+        change(&mut self.state.lock().log_expect("Failed to aquire lock."));
+    }
+
+    pub fn pause(&self) {
+        self.modify(|state| state.paused = true);
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.modify(|state| state.paused = false);
+        self.paused.send_replace(false);
+    }
+
+    // lowering it lets requests in flight finish, but holds back new ones
+    pub fn set_concurrency(&self, concurrency: usize) {
+        let concurrency = concurrency.max(1);
+        let mut state = self.state.lock().log_expect("Failed to aquire lock.");
+        let mut surplus = self.surplus.lock().log_expect("Failed to aquire lock.");
+        if concurrency > state.concurrency {
+            let added = concurrency - state.concurrency;
+            let kept = added.min(*surplus);
+            *surplus -= kept;
+            self.slots.add_permits(added - kept);
+        } else {
+            *surplus += state.concurrency - concurrency;
+            // free slots go at once, the others once their requests finish
+            while *surplus > 0 {
+                match self.slots.try_acquire() {
+                    Ok(slot) => slot.forget(),
+                    Err(_) => break,
+                }
+                *surplus -= 1;
+            }
+        }
+        state.concurrency = concurrency;
+    }
+
+    pub fn set_rate(&self, rate: Option<f64>) {
+        let rate = rate.and_then(bounded);
+        self.modify(|state| state.rate = rate);
+    }
+
+    // wait until not paused, below the concurrency limit and within the rate limit
+    pub async fn acquire(self: &Arc<Self>) -> Permit {
+        let permit = self.slot().await;
+        if let Some(rate) = self.state().rate {
+            let delay = {
+                let mut next = self
+                    .next_request
+                    .lock()
+                    .log_expect("Failed to aquire lock.");
+                let now = Instant::now();
+                let start = (*next).max(now);
+                *next = start + Duration::try_from_secs_f64(1.0 / rate).unwrap_or(Duration::ZERO);
+                start - now
+            };
+            tokio::time::sleep(delay).await;
+        }
+        permit
+    }
+
+    // the next free slot in the order asked for, then the end of any pause
+    async fn slot(self: &Arc<Self>) -> Permit {
+        let slot = Arc::clone(&self.slots)
+            .acquire_owned()
+            .await
+            .log_expect("Request slots were closed.");
+        let mut permit = Permit {
+            control: Arc::clone(self),
+            slot: Some(slot),
+            running: false,
+        };
+        let mut paused = self.paused.subscribe();
+        while *paused.borrow_and_update() {
+            paused
+                .changed()
+                .await
+                .log_expect("Pause state was dropped.");
+        }
+        self.modify(|state| state.running += 1);
+        permit.running = true;
+        permit
+    }
+
+    // apply a command and return the reply for the control socket
+    pub fn apply(&self, command: Command) -> String {
+        match command {
+            Command::Pause => self.pause(),
+            Command::Resume => self.resume(),
+            Command::Concurrency(concurrency) => self.set_concurrency(concurrency),
+            Command::Rate(rate) => self.set_rate(rate),
+            Command::Status => {}
+        }
+        self.state().to_string()
+    }
+
+    // take commands, one per line, from local connections until the crawl ends
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, address)) => {
+                    info!("Control connection from {}.", address);
+                    tokio::spawn(Arc::clone(&self).session(stream));
+                }
+                Err(error) => warn!("Accepting a control connection failed: {}.", error),
+            }
+        }
+    }
+
+    // a connection sending an overlong line or nothing for IDLE_TIMEOUT is closed
+    async fn session(self: Arc<Self>, stream: TcpStream) {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = Vec::new();
+            let mut limited = (&mut reader).take(COMMAND_LIMIT + 1);
+            let read = limited.read_until(b'\n', &mut line);
+            match tokio::time::timeout(IDLE_TIMEOUT, read).await {
+                Ok(Ok(0)) | Ok(Err(_)) => return,
+                Ok(Ok(_)) if !line.ends_with(b"\n") && line.len() as u64 > COMMAND_LIMIT => {
+                    let _ = writer.write_all(b"error: command too long\n").await;
+                    return;
+                }
+                Ok(Ok(_)) => {}
+                Err(_) => {
+                    info!("Closing an idle control connection.");
+                    return;
+                }
+            }
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match line.parse() {
+                Ok(command) => {
+                    info!("Control command: {}.", line.trim());
+                    self.apply(command)
+                }
+                Err(error) => error,
+            };
+            if writer
+                .write_all(format!("{}\n", reply).as_bytes())
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+impl fmt::Display for ControlState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} concurrency={} running={} rate=",
+            if self.paused { "paused" } else { "running" },
+            self.concurrency,
+            self.running
+        )?;
+        match self.rate {
+            Some(rate) => write!(f, "{}/s", rate),
+            None => write!(f, "off"),
+        }
+    }
+}
+
+// a usable rate, None for a rate that is not a positive number, e.g. NaN or inf
+fn bounded(rate: f64) -> Option<f64> {
+    (rate.is_finite() && rate > 0.0).then(|| rate.clamp(MIN_RATE, MAX_RATE))
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let argument = words.next();
+        let usage = || {
+            format!(
+                "error: unknown command \"{}\", expected pause, resume, status, concurrency <n> or rate <per second|off>",
+                line.trim()
+            )
+        };
+        match (command, argument) {
+            ("pause", None) => Ok(Command::Pause),
+            ("resume", None) => Ok(Command::Resume),
+            ("status", None) => Ok(Command::Status),
+            ("concurrency", Some(count)) => count
+                .parse()
+                .ok()
+                .filter(|count| *count > 0)
+                .map(Command::Concurrency)
+                .ok_or_else(|| format!("error: invalid concurrency {}", count)),
+            ("rate", Some("off")) => Ok(Command::Rate(None)),
+            ("rate", Some(rate)) => rate
+                .parse()
+                .ok()
+                .and_then(bounded)
+                .map(|rate| Command::Rate(Some(rate)))
+                .ok_or_else(|| format!("error: invalid rate {}", rate)),
+            _ => Err(usage()),
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if self.running {
+            self.control.modify(|state| state.running -= 1);
+        }
+        let Some(slot) = self.slot.take() else {
+            return;
+        };
+        let mut surplus = self
+            .control
+            .surplus
+            .lock()
+            .log_expect("Failed to aquire lock.");
+        if *surplus > 0 {
+            *surplus -= 1;
+            slot.forget();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn pausing_holds_back_requests() {
+        let control = Control::new(1);
+        control.pause();
+        let waiting = tokio::spawn({
+            let control = Arc::clone(&control);
+            async move {
+                control.acquire().await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        control.resume();
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("request was not released")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrency_can_change_while_running() {
+        let control = Control::new(1);
+        let first = control.acquire().await;
+        let second = tokio::spawn({
+            let control = Arc::clone(&control);
+            async move { control.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!second.is_finished());
+        control.set_concurrency(2);
+        let second = tokio::time::timeout(Duration::from_secs(1), second)
+            .await
+            .expect("concurrency was not raised")
+            .unwrap();
+        assert_eq!(control.state().running, 2);
+        drop((first, second));
+        assert_eq!(control.state().running, 0);
+    }
+
+    #[tokio::test]
+    async fn grants_slots_in_order() {
+        let control = Control::new(1);
+        let first = control.acquire().await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut waiting = Vec::new();
+        for job in 0..3 {
+            let (control, order) = (Arc::clone(&control), Arc::clone(&order));
+            waiting.push(tokio::spawn(async move {
+                let _permit = control.acquire().await;
+                order.lock().unwrap().push(job);
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        drop(first);
+        for job in waiting {
+            job.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), [0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn lowering_concurrency_waits_for_requests_in_flight() {
+        let control = Control::new(2);
+        let (first, second) = (control.acquire().await, control.acquire().await);
+        control.set_concurrency(1);
+        drop(first);
+        let third = tokio::spawn({
+            let control = Arc::clone(&control);
+            async move { control.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!third.is_finished());
+        drop(second);
+        let third = tokio::time::timeout(Duration::from_secs(1), third)
+            .await
+            .expect("slot was not handed back")
+            .unwrap();
+        assert_eq!(control.state().running, 1);
+        drop(third);
+    }
+
+    #[tokio::test]
+    async fn closes_sessions_sending_overlong_lines() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(Control::new(1).serve(listener));
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"status\n").await.unwrap();
+        stream.write_all(&[b'a'; 4096]).await.unwrap();
+        let mut replies = String::new();
+        stream.read_to_string(&mut replies).await.unwrap();
+        assert_eq!(
+            replies,
+            "running concurrency=1 running=0 rate=off\nerror: command too long\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limit_spaces_requests() {
+        let control = Control::new(4);
+        control.set_rate(Some(20.0));
+        let started = Instant::now();
+        for _ in 0..3 {
+            control.acquire().await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!("pause".parse(), Ok(Command::Pause));
+        assert_eq!(" concurrency 8 ".parse(), Ok(Command::Concurrency(8)));
+        assert_eq!("rate 0.5".parse(), Ok(Command::Rate(Some(0.5))));
+        assert_eq!("rate off".parse(), Ok(Command::Rate(None)));
+        assert_eq!("rate 1e-300".parse(), Ok(Command::Rate(Some(MIN_RATE))));
+        assert_eq!("rate 1e300".parse(), Ok(Command::Rate(Some(MAX_RATE))));
+        assert!("rate NaN".parse::<Command>().is_err());
+        assert!("rate inf".parse::<Command>().is_err());
+        assert!("concurrency 0".parse::<Command>().is_err());
+        assert!("faster".parse::<Command>().is_err());
+        let control = Control::new(2);
+        assert_eq!(
+            control.apply(Command::Rate(Some(1.5))),
+            "running concurrency=2 running=0 rate=1.5/s"
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    crawl, rematch, sink::ChannelSink, Config, Control, CrawlResults, DateRange, Entity, Event,
+    ExcludedTarget, ScannedTitle, Selectors, Sink, SuccessTarget, Url, Visited,
+};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+// a configured crawl, ready to run
+pub struct Crawler {
+    config: Config,
+}
+
+// builds a Crawler, anything left out keeps the defaults of Config
+pub struct CrawlerBuilder {
+    config: Config,
+}
+
+impl Crawler {
+    pub fn builder() -> CrawlerBuilder {
+        CrawlerBuilder {
+            config: Config::default(),
+        }
+    }
+
+    // hits as they are found, the stream ends when the crawl is over
+    pub fn hits(&mut self) -> UnboundedReceiver<SuccessTarget> {
+        let (tx, rx) = unbounded_channel();
+        self.config
+            .sinks
+            .push(Box::new(ChannelSink::new(tx, |event| match event {
+                Event::HitFound(hit) => Some(hit.clone()),
+                _ => None,
+            })));
+        rx
+    }
+
+    // every event of the crawl, ending with Event::Finished
+    pub fn events(&mut self) -> UnboundedReceiver<Event> {
+        let (tx, rx) = unbounded_channel();
+        self.config
+            .sinks
+            .push(Box::new(ChannelSink::new(tx, |event| Some(event.clone()))));
+        rx
+    }
+
+    // pause, resume or change the concurrency of the crawl while it runs
+    pub fn control(&self) -> Arc<Control> {
+        Arc::clone(&self.config.control)
+    }
+
+    // match the titles of an earlier crawl instead of crawling, see rematch
+    pub fn rematch(self, corpus: impl IntoIterator<Item = ScannedTitle>) -> CrawlResults {
+        rematch(self.config, corpus)
+    }
+
+    pub async fn run(self) -> CrawlResults {
+        crawl(self.config, std::future::pending()).await
+    }
+
+    // run until every page is handled or stop resolves, whichever comes first
+    pub async fn run_until(self, stop: impl Future<Output = ()>) -> CrawlResults {
+        crawl(self.config, stop).await
+    }
+}
+
+impl CrawlerBuilder {
+    pub fn site(mut self, root: Url) -> Self {
+        self.config.root = root;
+        self
+    }
+
+    pub fn keywords<T: Into<String>>(mut self, keywords: impl IntoIterator<Item = T>) -> Self {
+        self.config
+            .keywords
+            .extend(keywords.into_iter().map(Into::into));
+        self
+    }
+
+    // keyword groups, each hit is tagged with the name of its entity
+    pub fn entities(mut self, entities: impl IntoIterator<Item = Entity>) -> Self {
+        self.config.entities.extend(entities);
+        self
+    }
+
+    // OCR noise in titles and its replacement, e.g. ("＠", "") to drop a placeholder
+    pub fn ocr_substitutions<T: Into<String>>(
+        mut self,
+        substitutions: impl IntoIterator<Item = (T, T)>,
+    ) -> Self {
+        self.config.ocr_substitutions.extend(
+            substitutions
+                .into_iter()
+                .map(|(noise, replacement)| (noise.into(), replacement.into())),
+        );
+        self
+    }
+
+    // edits tolerated when a keyword is not found as is, 0 to only match exactly
+    pub fn max_edits(mut self, edits: usize) -> Self {
+        self.config.max_edits = edits;
+        self
+    }
+
+    pub fn date_range(mut self, range: DateRange) -> Self {
+        self.config.dates = Some(range);
+        self
+    }
+
+    pub fn pages(mut self, pages: impl IntoIterator<Item = u32>) -> Self {
+        self.config.pages.extend(pages);
+        self
+    }
+
+    // number of requests in flight at once
+    pub fn concurrency(self, concurrency: usize) -> Self {
+        self.config.control.set_concurrency(concurrency);
+        self
+    }
+
+    // requests started per second, None for no limit
+    pub fn rate_limit(self, rate: Option<f64>) -> Self {
+        self.config.control.set_rate(rate);
+        self
+    }
+
+    // attempts per page; 0 counts as 1
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.config.retries = retries;
+        self
+    }
+
+    // the wait before the first retry of a page, doubled for each further one
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.config.retry_delay = delay;
+        self
+    }
+
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.config.client = client;
+        self
+    }
+
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.config.lenient = lenient;
+        self
+    }
+
+    pub fn resume(mut self, visited: Visited) -> Self {
+        self.config.visited = visited;
+        self
+    }
+
+    // the hits and ruled out titles of the run being resumed, so that the results hold both
+    pub fn previous_hits(
+        mut self,
+        successes: Vec<SuccessTarget>,
+        excluded: Vec<ExcludedTarget>,
+    ) -> Self {
+        self.config.previous_successes = successes;
+        self.config.previous_excluded = excluded;
+        self
+    }
+
+    // css selectors of the archive markup, see Selectors::load
+    pub fn selectors(mut self, selectors: Selectors) -> Self {
+        self.config.selectors = selectors;
+        self
+    }
+
+    // keep every examined title in the results, after those of a previous run
+    pub fn corpus(mut self, previous: impl IntoIterator<Item = ScannedTitle>) -> Self {
+        self.config.corpus = Some(previous.into_iter().collect());
+        self
+    }
+
+    // controls of the crawl to be built, e.g. for a UI that steers it
+    pub fn control(&self) -> Arc<Control> {
+        Arc::clone(&self.config.control)
+    }
+
+    pub fn sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.config.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn build(self) -> Crawler {
+        Crawler {
+            config: self.config,
+        }
+    }
+}
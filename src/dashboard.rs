@@ -0,0 +1,405 @@
+use crate::{Control, Event, FailCategory, LogExpect, Sink, SuccessTarget, TargetType};
+use crossterm::event::{self as input, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::{cursor, queue, style, terminal};
+use std::collections::{HashSet, VecDeque};
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const LEVELS: [(TargetType, &str); 5] = [
+    (TargetType::Index, "index"),
+    (TargetType::Year, "years"),
+    (TargetType::Month, "months"),
+    (TargetType::Day, "days"),
+    (TargetType::Page, "pages"),
+];
+const BAR_WIDTH: usize = 40;
+const LATEST_HITS: usize = 10;
+// request rate is measured over this window
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+const REFRESH: Duration = Duration::from_millis(250);
+// highest rate limit the keys step to before lifting it, in requests per second
+const TOP_RATE: f64 = 16.0;
+
+#[derive(Default, Clone, Copy)]
+struct LevelCount {
+    discovered: usize,
+    done: usize,
+}
+
+struct State {
+    started: Instant,
+    levels: [LevelCount; LEVELS.len()],
+    // when recent requests were sent, for the request rate
+    requests: VecDeque<Instant>,
+    request_count: u64,
+    error_count: u64,
+    // urls that failed and are being tried again
+    retrying: HashSet<String>,
+    latest_hits: VecDeque<SuccessTarget>,
+    excluded: u64,
+    running: usize,
+    successes: u64,
+    count: u64,
+    finished: bool,
+}
+
+// a live view of a crawl in the terminal, fed as a sink and drawn on its own thread
+#[derive(Clone)]
+pub struct Dashboard {
+    state: Arc<Mutex<State>>,
+    control: Arc<Control>,
+}
+
+fn slot(level: TargetType) -> Option<usize> {
+    LEVELS.iter().position(|(each, _)| *each == level)
+}
+
+impl State {
+    fn new() -> Self {
+        let mut levels = [LevelCount::default(); LEVELS.len()];
+        levels[0].discovered = 1;
+        State {
+            started: Instant::now(),
+            levels,
+            requests: VecDeque::new(),
+            request_count: 0,
+            error_count: 0,
+            retrying: HashSet::new(),
+            latest_hits: VecDeque::new(),
+            excluded: 0,
+            running: 0,
+            successes: 0,
+            count: 0,
+            finished: false,
+        }
+    }
+
+    fn request(&mut self, failed: bool) {
+        let now = Instant::now();
+        self.requests.push_back(now);
+        while self
+            .requests
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) > RATE_WINDOW)
+        {
+            self.requests.pop_front();
+        }
+        self.request_count += 1;
+        if failed {
+            self.error_count += 1;
+        }
+    }
+
+    fn update(&mut self, event: &Event) {
+        match event {
+            Event::PageFetched { url, .. } => {
+                self.retrying.remove(url);
+                self.request(false);
+            }
+            Event::RequestRetried { url, .. } => {
+                self.retrying.insert(url.clone());
+                self.request(true);
+            }
+            Event::PageFailed(failure) => {
+                self.retrying.remove(&failure.url);
+                // parsing failures were fetched fine and already counted
+                if failure.category != FailCategory::Parsing {
+                    self.request(true);
+                }
+                if let Some(index) = slot(failure.target_type) {
+                    self.levels[index].done += 1;
+                }
+            }
+            Event::LevelExpanded { level, pages, .. } => {
+                if let Some(index) = slot(*level) {
+                    self.levels[index].done += 1;
+                    if let Some(child) = self.levels.get_mut(index + 1) {
+                        child.discovered += pages;
+                    }
+                }
+            }
+            Event::HitFound(hit) => {
+                self.latest_hits.push_front(hit.clone());
+                self.latest_hits.truncate(LATEST_HITS);
+            }
+            Event::HitExcluded(_) => self.excluded += 1,
+            Event::Progress {
+                running,
+                successes,
+                count,
+            } => {
+                self.running = *running;
+                self.successes = *successes;
+                self.count = *count;
+            }
+            Event::Finished { .. } => self.finished = true,
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        let window = self.started.elapsed().min(RATE_WINDOW).as_secs_f64();
+        if window > 0.0 {
+            self.requests.len() as f64 / window
+        } else {
+            0.0
+        }
+    }
+
+    // time left for the pages discovered so far, at the pace of the crawl until now
+    fn eta(&self) -> Option<Duration> {
+        let done: usize = self.levels.iter().map(|level| level.done).sum();
+        let pending: usize = self
+            .levels
+            .iter()
+            .map(|level| level.discovered.saturating_sub(level.done))
+            .sum();
+        if done == 0 {
+            return None;
+        }
+        Some(self.started.elapsed().mul_f64(pending as f64 / done as f64))
+    }
+
+    fn lines(&self, control: &Control) -> Vec<String> {
+        let controls = control.state();
+        let mut lines = vec![format!(
+            "[webb] {}  {} task(s) running, {}/{} request(s) in flight, rate limit {}",
+            if controls.paused {
+                "PAUSED"
+            } else {
+                "crawling"
+            },
+            self.running,
+            controls.running,
+            controls.concurrency,
+            controls
+                .rate
+                .map_or("off".to_string(), |rate| format!("{}/s", rate)),
+        )];
+        lines.push(String::new());
+        for ((_, name), level) in LEVELS.iter().zip(self.levels.iter()) {
+            let filled = (level.done * BAR_WIDTH)
+                .checked_div(level.discovered)
+                .unwrap_or(0)
+                .min(BAR_WIDTH);
+            lines.push(format!(
+                "{:>7} [{}{}] {}/{}",
+                name,
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                level.done,
+                level.discovered
+            ));
+        }
+        lines.push(String::new());
+        let error_rate = match self.request_count {
+            0 => 0.0,
+            requests => self.error_count as f64 * 100.0 / requests as f64,
+        };
+        lines.push(format!(
+            "{:.1} request(s)/s, {:.1}% errors, {} retrying, elapsed {}, ETA {}",
+            self.rate(),
+            error_rate,
+            self.retrying.len(),
+            clock(self.started.elapsed()),
+            self.eta().map_or("-".to_string(), clock),
+        ));
+        lines.push(format!(
+            "{} hit(s), {} ruled out, in {} examined",
+            self.successes, self.excluded, self.count
+        ));
+        lines.push(String::new());
+        lines.push("Latest hits:".to_string());
+        for hit in &self.latest_hits {
+            let fuzzy = match hit.confidence {
+                Some(confidence) if confidence < 1.0 => format!(" (~{:.0}%)", confidence * 100.0),
+                _ => String::new(),
+            };
+            lines.push(format!(
+                "  {} {}{}",
+                hit.page.as_deref().unwrap_or(""),
+                hit.title,
+                fuzzy
+            ));
+        }
+        lines.push(String::new());
+        lines.push("p: pause/resume  +/-: concurrency  [/]: rate limit  q: quit".to_string());
+        lines
+    }
+}
+
+fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+impl Dashboard {
+    pub fn new(control: Arc<Control>) -> Self {
+        Dashboard {
+            state: Arc::new(Mutex::new(State::new())),
+            control,
+        }
+    }
+
+    // take over the terminal for the dashboard, before anything logs to it; fails without
+    // one, e.g. under cron or with output redirected, so that the crawl logs instead
+    pub fn enter_terminal() -> Result<(), String> {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return Err("not a terminal".to_string());
+        }
+        terminal::enable_raw_mode().map_err(|error| error.to_string())?;
+        queue!(
+            std::io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )
+        .map_err(|error| {
+            let _ = terminal::disable_raw_mode();
+            error.to_string()
+        })
+    }
+
+    // draw until the crawl finishes, once enter_terminal succeeded; quit is called when the
+    // user asks to stop
+    pub fn spawn(&self, quit: impl FnOnce() + Send + 'static) -> std::thread::JoinHandle<()> {
+        let dashboard = self.clone();
+        std::thread::spawn(move || {
+            let mut quit = Some(quit);
+            let mut out = std::io::stdout();
+            loop {
+                dashboard.draw(&mut out);
+                if dashboard.finished() {
+                    break;
+                }
+                if input::poll(REFRESH).unwrap_or(false) {
+                    if let Ok(input::Event::Key(key)) = input::read() {
+                        if dashboard.key(key) {
+                            if let Some(quit) = quit.take() {
+                                quit();
+                            }
+                        }
+                    }
+                }
+            }
+            let _ = queue!(out, cursor::Show, terminal::LeaveAlternateScreen);
+            let _ = out.flush();
+            let _ = terminal::disable_raw_mode();
+        })
+    }
+
+    fn finished(&self) -> bool {
+        self.state
+            .lock()
+            .log_expect("Failed to aquire lock.")
+            .finished
+    }
+
+    // handle a key press, returns whether the user wants to quit
+    fn key(&self, key: KeyEvent) -> bool {
+        let control = &self.control;
+        match key.code {
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                if control.state().paused {
+                    control.resume();
+                } else {
+                    control.pause();
+                }
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                control.set_concurrency(control.state().concurrency + 1)
+            }
+            KeyCode::Char('-') => control.set_concurrency(control.state().concurrency - 1),
+            // halve or double the rate limit, doubling past the top removes it
+            KeyCode::Char('[') => control.set_rate(Some(
+                control.state().rate.map_or(TOP_RATE, |rate| rate / 2.0),
+            )),
+            KeyCode::Char(']') => control.set_rate(
+                control
+                    .state()
+                    .rate
+                    .map(|rate| rate * 2.0)
+                    .filter(|rate| *rate <= TOP_RATE),
+            ),
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            // raw mode swallows the Ctrl-C signal
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+            _ => {}
+        }
+        false
+    }
+
+    fn draw(&self, out: &mut impl Write) {
+        let lines = self
+            .state
+            .lock()
+            .log_expect("Failed to aquire lock.")
+            .lines(&self.control);
+        let _ = queue!(out, cursor::MoveTo(0, 0));
+        for line in lines {
+            let _ = queue!(
+                out,
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                style::Print(line),
+                cursor::MoveToNextLine(1)
+            );
+        }
+        let _ = queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown));
+        let _ = out.flush();
+    }
+}
+
+impl Sink for Dashboard {
+    fn event(&self, event: &Event) {
+        self.state
+            .lock()
+            .log_expect("Failed to aquire lock.")
+            .update(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_count_discovered_and_done_pages() {
+        let mut state = State::new();
+        state.update(&Event::LevelExpanded {
+            url: "index".to_string(),
+            level: TargetType::Index,
+            pages: 3,
+            articles: 0,
+        });
+        state.update(&Event::RequestRetried {
+            url: "year".to_string(),
+            attempt: 1,
+            category: FailCategory::Timeout,
+            status: None,
+        });
+        assert_eq!(state.retrying.len(), 1);
+        state.update(&Event::PageFetched {
+            url: "year".to_string(),
+            level: TargetType::Year,
+            status: 200,
+            bytes: 0,
+            elapsed: Duration::ZERO,
+        });
+        state.update(&Event::LevelExpanded {
+            url: "year".to_string(),
+            level: TargetType::Year,
+            pages: 12,
+            articles: 0,
+        });
+        assert!(state.retrying.is_empty());
+        assert_eq!((state.levels[0].done, state.levels[0].discovered), (1, 1));
+        assert_eq!((state.levels[1].done, state.levels[1].discovered), (1, 3));
+        assert_eq!(state.levels[2].discovered, 12);
+        assert_eq!((state.request_count, state.error_count), (2, 1));
+        assert!(state.eta().is_some());
+    }
+}
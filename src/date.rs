@@ -0,0 +1,164 @@
+use crate::links::Url;
+use std::fmt;
+use std::str::FromStr;
+
+// an issue date, as spelled in archive urls: /renminribao/1958/3/5/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub const fn new(year: u32, month: u32, day: u32) -> Self {
+        Date { year, month, day }
+    }
+
+    // date of a day, page or article url below root
+    pub fn from_url(root: &Url, url: &Url) -> Option<Date> {
+        match date_prefix(root, url)[..] {
+            [year, month, day, ..] => Some(Date::new(year, month, day)),
+            _ => None,
+        }
+    }
+
+    // like parsing, but a year or month stands for its last day, e.g. 1966 for 1966-12-31
+    // and 1966-2 for 1966-02-28; for the end of a range, such as --to
+    pub fn parse_last(text: &str) -> Result<Date, String> {
+        let (date, parts) = parse(text)?;
+        Ok(match parts {
+            1 => Date::new(date.year, 12, 31),
+            2 => Date::new(date.year, date.month, days_in_month(date.year, date.month)),
+            _ => date,
+        })
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// the date with missing parts set to the first, and the number of parts given
+fn parse(text: &str) -> Result<(Date, usize), String> {
+    let parts = text
+        .trim()
+        .split('-')
+        .map(|part| part.parse::<u32>())
+        .collect::<Result<Vec<u32>, _>>()
+        .map_err(|_| format!("invalid date \"{}\"", text))?;
+    let date = match parts[..] {
+        [year] => Date::new(year, 1, 1),
+        [year, month] => Date::new(year, month, 1),
+        [year, month, day] => Date::new(year, month, day),
+        _ => return Err(format!("invalid date \"{}\"", text)),
+    };
+    if !(1..=12).contains(&date.month) || !(1..=31).contains(&date.day) {
+        return Err(format!("invalid date \"{}\"", text));
+    }
+    Ok((date, parts.len()))
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    // 1958-03-05, 1958-3-5 or just 1958, for 1958-01-01; see Date::parse_last for ends
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse(text).map(|(date, _)| date)
+    }
+}
+
+// read from strings such as "1958-03-05", e.g. in crawl profiles
+impl<'de> serde::Deserialize<'de> for Date {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// reads an optional end of a range with Date::parse_last, e.g. for `to` keys
+pub fn deserialize_last<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Date>, D::Error> {
+    use serde::Deserialize;
+    Option::<String>::deserialize(deserializer)?
+        .map(|text| Date::parse_last(&text))
+        .transpose()
+        .map_err(serde::de::Error::custom)
+}
+
+// written as "1958-03-05"
+impl serde::Serialize for Date {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+// inclusive range of issue dates to crawl
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub from: Date,
+    pub to: Date,
+}
+
+impl DateRange {
+    pub fn new(from: Date, to: Date) -> Self {
+        DateRange { from, to }
+    }
+
+    pub fn contains(&self, date: Date) -> bool {
+        self.from <= date && date <= self.to
+    }
+
+    // whether a year, month or day listing can contain dates within the range
+    pub fn overlaps(&self, prefix: &[u32]) -> bool {
+        let (earliest, latest) = match prefix {
+            [] => return true,
+            [year] => (Date::new(*year, 1, 1), Date::new(*year, 12, 31)),
+            [year, month] => (Date::new(*year, *month, 1), Date::new(*year, *month, 31)),
+            [year, month, day, ..] => (
+                Date::new(*year, *month, *day),
+                Date::new(*year, *month, *day),
+            ),
+        };
+        earliest <= self.to && latest >= self.from
+    }
+}
+
+// the numeric path segments of url below root, e.g. [1958, 3, 5, 7]
+pub fn date_prefix(root: &Url, url: &Url) -> Vec<u32> {
+    url.path()
+        .strip_prefix(root.path())
+        .unwrap_or_default()
+        .split('/')
+        .map_while(|segment| segment.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ends_of_ranges_cover_their_period() {
+        assert_eq!("1966".parse(), Ok(Date::new(1966, 1, 1)));
+        assert_eq!(Date::parse_last("1966"), Ok(Date::new(1966, 12, 31)));
+        assert_eq!(Date::parse_last("1966-2"), Ok(Date::new(1966, 2, 28)));
+        assert_eq!(Date::parse_last("1960-02"), Ok(Date::new(1960, 2, 29)));
+        assert_eq!(Date::parse_last("1966-4-5"), Ok(Date::new(1966, 4, 5)));
+        assert!(Date::parse_last("1966-13").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::Date;
+use serde::de::{self, value::MapAccessDeserializer, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+// an alias spelled out as a map, see Alias
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct AliasMap {
+    keyword: String,
+    #[serde(default)]
+    from: Option<Date>,
+    #[serde(default, deserialize_with = "crate::date::deserialize_last")]
+    to: Option<Date>,
+    #[serde(default)]
+    edits: Option<usize>,
+    #[serde(default)]
+    followed_by: Vec<String>,
+    #[serde(default)]
+    preceded_by: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+// one name of an entity, e.g. 华中工学院, optionally only valid for issues within its dates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alias {
+    pub keyword: String,
+    pub from: Option<Date>,
+    pub to: Option<Date>,
+    // edits tolerated when the keyword is not found as is, overriding those of the crawl
+    pub edits: Option<usize>,
+    // the keyword only counts right before one of these, e.g. 大学 or 医学院 after 同济
+    pub followed_by: Vec<String>,
+    // the keyword only counts right after one of these
+    pub preceded_by: Vec<String>,
+    // titles containing one of these are ruled out, e.g. 安危同济
+    pub exclude: Vec<String>,
+    // e.g. university or hospital, copied to the hits of the alias
+    pub category: Option<String>,
+    // remarks of whoever maintains the keyword list; not used for matching
+    pub notes: Option<String>,
+    // where the alias was read from, e.g. keywords.csv:12, recorded on its hits
+    pub row: Option<String>,
+}
+
+impl Alias {
+    pub fn new(keyword: impl Into<String>) -> Self {
+        Alias {
+            keyword: keyword.into(),
+            from: None,
+            to: None,
+            edits: None,
+            followed_by: Vec::new(),
+            preceded_by: Vec::new(),
+            exclude: Vec::new(),
+            category: None,
+            notes: None,
+            row: None,
+        }
+    }
+
+    pub fn dates(mut self, from: Option<Date>, to: Option<Date>) -> Self {
+        self.from = from;
+        self.to = to;
+        self
+    }
+
+    // whether the alias only counts next to certain words; such aliases are matched exactly
+    pub fn has_context(&self) -> bool {
+        !self.followed_by.is_empty() || !self.preceded_by.is_empty()
+    }
+
+    // whether the alias was in use on the issue date; undated articles match any alias
+    pub fn valid_on(&self, date: Option<Date>) -> bool {
+        date.is_none_or(|date| {
+            self.from.is_none_or(|from| from <= date) && self.to.is_none_or(|to| date <= to)
+        })
+    }
+}
+
+// either just the keyword or a map with keyword, from, to, edits, followed-by, preceded-by,
+// exclude, category and notes
+impl<'de> Deserialize<'de> for Alias {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AliasVisitor;
+
+        impl<'de> Visitor<'de> for AliasVisitor {
+            type Value = Alias;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a keyword or a map with keyword and its rules")
+            }
+
+            fn visit_str<E: de::Error>(self, keyword: &str) -> Result<Alias, E> {
+                Ok(Alias::new(keyword))
+            }
+
+            fn visit_map<M: MapAccess<'de>>(self, map: M) -> Result<Alias, M::Error> {
+                let alias = AliasMap::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Alias {
+                    keyword: alias.keyword,
+                    from: alias.from,
+                    to: alias.to,
+                    edits: alias.edits,
+                    followed_by: alias.followed_by,
+                    preceded_by: alias.preceded_by,
+                    exclude: alias.exclude,
+                    category: alias.category,
+                    notes: alias.notes,
+                    row: None,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(AliasVisitor)
+    }
+}
+
+// a canonical entity such as an institution, found under any of its names
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Entity {
+    pub name: String,
+    pub aliases: Vec<Alias>,
+}
+
+impl Entity {
+    pub fn new<T: Into<String>>(
+        name: impl Into<String>,
+        aliases: impl IntoIterator<Item = T>,
+    ) -> Self {
+        Entity {
+            name: name.into(),
+            aliases: aliases.into_iter().map(Alias::new).collect(),
+        }
+    }
+
+    // a plain keyword, standing for itself
+    pub fn keyword(keyword: &str) -> Self {
+        Entity::new(keyword, [keyword])
+    }
+
+    // the first problem with the entity, if any
+    pub fn check(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("entity without a name".to_string());
+        }
+        if self.aliases.is_empty() {
+            return Err(format!("{}: lists no aliases", self.name));
+        }
+        for alias in &self.aliases {
+            if alias.keyword.trim().is_empty() {
+                return Err(format!("{}: empty alias", self.name));
+            }
+            let rules = alias.followed_by.iter().chain(&alias.preceded_by);
+            if rules
+                .chain(&alias.exclude)
+                .any(|rule| rule.trim().is_empty())
+            {
+                return Err(format!(
+                    "{}: {} has an empty rule",
+                    self.name, alias.keyword
+                ));
+            }
+            if let (Some(from), Some(to)) = (alias.from, alias.to) {
+                if from > to {
+                    return Err(format!(
+                        "{}: {} is valid to {}, before from ({})",
+                        self.name, alias.keyword, to, from
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_are_valid_within_their_dates() {
+        let entities: Vec<Entity> = serde_yaml::from_str(
+            "- name: HUST\n  aliases:\n    - keyword: 华中工学院\n      to: 1988-01-14\n    - 华中科技大学\n",
+        )
+        .unwrap();
+        let aliases = &entities[0].aliases;
+        assert_eq!(aliases[1], Alias::new("华中科技大学"));
+        assert!(aliases[0].valid_on(Some(Date::new(1958, 3, 5))));
+        assert!(!aliases[0].valid_on(Some(Date::new(1990, 1, 1))));
+        assert!(aliases[0].valid_on(None));
+        assert!(entities[0].check().is_ok());
+        assert!(Entity::new("HUST", Vec::<String>::new()).check().is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{ExcludedTarget, FailCategory, FailTarget, Sink, SuccessTarget, TargetType};
+use std::sync::Arc;
+use std::time::Duration;
+
+// what happens during a crawl, in the order it happens
+#[derive(serde::Serialize, Debug, Clone)]
+pub enum Event {
+    PageFetched {
+        url: String,
+        level: TargetType,
+        // the HTTP status of the response, e.g. 200
+        status: u16,
+        bytes: usize,
+        // the time of the successful request alone
+        elapsed: Duration,
+    },
+    // a request failed and is sent again, the page is done once fetched or failed
+    RequestRetried {
+        url: String,
+        attempt: u32,
+        category: FailCategory,
+        // the HTTP status, if there was a response, e.g. 500
+        status: Option<u16>,
+    },
+    PageFailed(FailTarget),
+    HitFound(SuccessTarget),
+    // a title contained a keyword but one of its rules ruled it out
+    HitExcluded(ExcludedTarget),
+    // a listing was parsed into pages of the next level and articles
+    LevelExpanded {
+        url: String,
+        level: TargetType,
+        pages: usize,
+        articles: usize,
+    },
+    // sent whenever the number of running jobs changes
+    Progress {
+        running: usize,
+        successes: u64,
+        count: u64,
+    },
+    Finished {
+        successes: usize,
+        failures: usize,
+        count: u64,
+    },
+}
+
+// hands every event to every sink
+#[derive(Clone)]
+pub struct Emitter {
+    sinks: Arc<Vec<Box<dyn Sink>>>,
+}
+
+impl Emitter {
+    pub fn new(sinks: Vec<Box<dyn Sink>>) -> Self {
+        Emitter {
+            sinks: Arc::new(sinks),
+        }
+    }
+
+    pub fn emit(&self, event: Event) {
+        for sink in self.sinks.iter() {
+            sink.event(&event);
+        }
+    }
+
+    pub fn sinks(&self) -> &[Box<dyn Sink>] {
+        &self.sinks
+    }
+}
@@ -1,47 +1,173 @@
-const RETRIES: i32 = 5;
-
-use crate::{Error, LogExpect};
+use crate::{event::Emitter, Control, Error, Event, FailCategory, LogExpect};
 use log::{trace, warn};
+use reqwest::StatusCode;
+use std::hash::{BuildHasher, Hasher};
 use std::sync::Arc;
-use tokio::sync::Semaphore;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// the longest wait between two attempts, whether backing off or told by Retry-After
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
 
 pub struct FetchClient {
-    throttler: Arc<Semaphore>,
+    client: reqwest::Client,
+    control: Arc<Control>,
+    retries: u32,
+    // the wait before the first retry, doubled for each further one
+    retry_delay: Duration,
+    events: Emitter,
 }
 
 impl FetchClient {
-    pub fn new(throttle_count: usize) -> Self {
+    pub fn new(
+        client: reqwest::Client,
+        control: Arc<Control>,
+        retries: u32,
+        retry_delay: Duration,
+        events: Emitter,
+    ) -> Self {
         FetchClient {
-            throttler: Arc::new(Semaphore::new(throttle_count)),
+            client,
+            control,
+            // every page is requested at least once
+            retries: retries.max(1),
+            retry_delay,
+            events,
         }
     }
 
-    pub async fn fetch(&self, url: &str) -> Result<String, Error> {
-        let _permit = Arc::clone(&self.throttler)
-            .acquire_owned()
-            .await
-            .log_expect("Failed to aquire permit.");
+    // the page, its HTTP status and how long its successful request took
+    pub async fn fetch(&self, url: &str) -> Result<(String, u16, Duration), Error> {
+        let _permit = self.control.acquire().await;
         trace!("Fetching {}.", url);
-        let mut retry = RETRIES;
-        while retry > 0 {
-            retry -= 1;
-            match reqwest::get(url).await {
-                Ok(response) => match response.text().await {
-                    Ok(inner) => {
-                        return Ok(inner);
-                    }
-                    Err(error) => {
-                        warn!(
-                            "Decoding response from {} failed: {}, retrying[{}].",
-                            url, error, retry
-                        );
+        let mut attempts = 0;
+        let mut last_error = None;
+        let mut last_status = None;
+        while attempts < self.retries {
+            attempts += 1;
+            let retry = self.retries - attempts;
+            let sent = Instant::now();
+            let mut retry_after = None;
+            match self.client.get(url).send().await.and_then(|response| {
+                retry_after = told_to_wait(&response);
+                response.error_for_status()
+            }) {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    match response.text().await {
+                        Ok(inner) => {
+                            return Ok((inner, status, sent.elapsed()));
+                        }
+                        Err(error) => {
+                            warn!(
+                                "Decoding response from {} failed: {}, retrying[{}].",
+                                url, error, retry
+                            );
+                            self.retried(url, attempts, retry, &error, Some(status));
+                            last_status = Some(status);
+                            last_error = Some(error);
+                        }
                     }
-                },
+                }
                 Err(error) => {
                     warn!("Fetching {} failed: {}, retrying[{}].", url, error, retry);
+                    // client errors such as 404 will not go away by retrying, unlike
+                    // throttling with 429 Too Many Requests or 408 Request Timeout
+                    let permanent = error.status().is_some_and(|status| {
+                        status.is_client_error()
+                            && status != StatusCode::TOO_MANY_REQUESTS
+                            && status != StatusCode::REQUEST_TIMEOUT
+                    });
+                    last_status = error.status().map(|status| status.as_u16());
+                    if !permanent {
+                        self.retried(url, attempts, retry, &error, last_status);
+                    }
+                    last_error = Some(error);
+                    if permanent {
+                        break;
+                    }
                 }
             }
+            if retry > 0 {
+                let delay = retry_after.unwrap_or_else(|| self.backoff(attempts));
+                tokio::time::sleep(delay.min(MAX_RETRY_DELAY)).await;
+            }
         }
-        return Err(Error::NetworkError);
+        let error = last_error.log_expect("Fetch loop exited without an error.");
+        Err(Error::NetworkError {
+            category: categorize(&error),
+            message: error.to_string(),
+            attempts,
+            status: last_status,
+        })
+    }
+
+    // the wait after the given failed attempt: retry_delay doubled for each attempt before,
+    // less up to half of it at random, so that pages failing together are not retried together
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .retry_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(MAX_RETRY_DELAY);
+        delay.mul_f64(1.0 - jitter() / 2.0)
+    }
+
+    // tell listeners a failed request is about to be sent again
+    fn retried(
+        &self,
+        url: &str,
+        attempt: u32,
+        retry: u32,
+        error: &reqwest::Error,
+        status: Option<u16>,
+    ) {
+        if retry > 0 {
+            self.events.emit(Event::RequestRetried {
+                url: url.to_string(),
+                attempt,
+                category: categorize(error),
+                status,
+            });
+        }
+    }
+}
+
+// the wait a throttling response asks for in seconds with Retry-After; HTTP dates are
+// not read and leave the wait to the backoff
+fn told_to_wait(response: &reqwest::Response) -> Option<Duration> {
+    let status = response.status();
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+// a number in [0, 1) that differs from call to call, from the randomly keyed std hasher
+fn jitter() -> f64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn categorize(error: &reqwest::Error) -> FailCategory {
+    if error.is_timeout() {
+        FailCategory::Timeout
+    } else if error.is_status() {
+        FailCategory::HttpStatus
+    } else if error.is_connect() {
+        FailCategory::Connection
+    } else if error.is_decode() || error.is_body() {
+        FailCategory::Decode
+    } else {
+        FailCategory::Connection
     }
 }
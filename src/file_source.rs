@@ -3,7 +3,7 @@ use log::{error, info, warn};
 use std::env;
 use std::fs;
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::abort;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -31,19 +31,20 @@ impl FileSource {
             let since_the_epoch = time_start
                 .duration_since(UNIX_EPOCH)
                 .log_expect("Error: Time went backwards. How?");
-            let new_filename = format!(
+            // next to the file, e.g. in the output directory
+            let new_filename = file.with_file_name(format!(
                 "{}-{}",
                 since_the_epoch.as_secs(),
                 self.file
                     .file_name()
                     .log_expect("Error: File {} does not have a filename.",)
                     .to_string_lossy()
-            );
-            fs::rename(file, new_filename.as_str()).log_expect(&format!(
+            ));
+            fs::rename(file, &new_filename).log_expect(&format!(
                 "Error: Failed to create new file {}.",
-                new_filename
+                new_filename.to_string_lossy()
             ));
-            info!("Backup created: {}", new_filename);
+            info!("Backup created: {}", new_filename.to_string_lossy());
         }
         let mut open_file = fs::File::create(file).log_expect(&format!(
             "Error: Failed to create file {}.",
@@ -62,7 +63,47 @@ impl FileSource {
         info!("File {} saved.", file.to_string_lossy());
     }
 
-    pub fn load<'a, T>(&self) -> Option<T>
+    // like load, but fails with the key at fault; .toml files are read as toml
+    pub fn try_load<T>(&self) -> Result<T, String>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let file = self.file.as_path();
+        info!("Loading file {}.", file.to_string_lossy());
+        let text = fs::read_to_string(file)
+            .map_err(|error| format!("{}: {}", file.to_string_lossy(), error))?;
+        let located = |path: String, error: String| {
+            if path == "." {
+                return format!("{}: {}", file.to_string_lossy(), error);
+            }
+            // yaml errors may name a part of the path themselves
+            let error = match error.split_once(": ") {
+                Some((head, rest)) if path.starts_with(head) => rest.to_string(),
+                _ => error,
+            };
+            format!("{}: {}: {}", file.to_string_lossy(), path, error)
+        };
+        if file
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            serde_path_to_error::deserialize(&mut toml::Deserializer::new(&text))
+                .map_err(|error| located(error.path().to_string(), error.inner().to_string()))
+        } else {
+            serde_path_to_error::deserialize(serde_yaml::Deserializer::from_str(&text))
+                .map_err(|error| located(error.path().to_string(), error.inner().to_string()))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn exists(&self) -> bool {
+        self.file.exists()
+    }
+
+    pub fn load<T>(&self) -> Option<T>
     where
         T: serde::de::DeserializeOwned,
     {
@@ -84,3 +125,24 @@ impl FileSource {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_backups_next_to_the_file() {
+        let directory = env::temp_dir().join(format!("webb-file-source-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let source = FileSource::new(directory.join("success.yaml"));
+        source.store(&vec!["first"]);
+        source.store(&vec!["second"]);
+        let backups: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with("-success.yaml"))
+            .collect();
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(backups.len(), 1);
+    }
+}
@@ -1,59 +1,45 @@
-use crate::{log_expect::LogExpect, COUNT, SUCCESSES};
-use crossterm::{execute, terminal};
+use crate::{event::Emitter, Event, Progress};
 use log::info;
-use std::io::stdout;
-use std::sync::{atomic::Ordering, mpsc::Sender};
+use std::sync::{atomic::Ordering, Arc};
+use tokio::sync::Notify;
 
 pub struct JobManager {
-    tx: Option<Sender<()>>,
+    done: Arc<Notify>,
+    progress: Arc<Progress>,
+    events: Emitter,
     count: usize,
 }
 
 impl JobManager {
-    pub fn init(&mut self, tx: Sender<()>) {
-        self.tx = Some(tx);
-        execute!(stdout(), terminal::SetTitle("[webb] Initializing"))
-            .log_expect("Failed to set terminal title.");
+    pub fn new(done: Arc<Notify>, progress: Arc<Progress>, events: Emitter) -> Self {
+        JobManager {
+            done,
+            progress,
+            events,
+            count: 0,
+        }
     }
 
-    pub fn new() -> Self {
-        JobManager { tx: None, count: 0 }
+    fn report(&self) {
+        self.events.emit(Event::Progress {
+            running: self.count,
+            successes: self.progress.successes.load(Ordering::Relaxed),
+            count: self.progress.count.load(Ordering::Relaxed),
+        });
     }
 
     pub fn allocate(&mut self) {
         self.count += 1;
-        execute!(
-            stdout(),
-            terminal::SetTitle(&format!(
-                "[webb] Scraping: {} task(s) running. [{}/{}]",
-                self.count,
-                SUCCESSES.load(Ordering::Relaxed),
-                COUNT.load(Ordering::Relaxed)
-            ))
-        )
-        .log_expect("Failed to set terminal title.");
+        self.report();
     }
 
     pub fn deallocate(&mut self) {
         self.count -= 1;
-        execute!(
-            stdout(),
-            terminal::SetTitle(&format!(
-                "[webb] Scraping: {} task(s) running. [{}/{}]",
-                self.count,
-                SUCCESSES.load(Ordering::Relaxed),
-                COUNT.load(Ordering::Relaxed)
-            ))
-        )
-        .log_expect("Failed to set terminal title.");
+        self.report();
 
         if self.count == 0 {
             info!("Job done. Sending termination signal.");
-            self.tx
-                .as_ref()
-                .log_expect("Failed to get termination signal sender.")
-                .send(())
-                .log_expect("Failed to send termination signal.");
+            self.done.notify_one();
         }
     }
 }
@@ -0,0 +1,162 @@
+use crate::{Alias, Date, Entity, FileSource};
+use std::path::Path;
+
+// one row of a keyword list, e.g. 华中工学院,HUST,university,1952-01-01,1988-12-31,renamed in 1988
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Row {
+    keyword: String,
+    #[serde(default)]
+    entity: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    from: Option<Date>,
+    #[serde(default, deserialize_with = "crate::date::deserialize_last")]
+    to: Option<Date>,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+// a keyword list kept without touching the code: a CSV file with the header
+// keyword,entity,category,from,to,notes and # comments, or a YAML list of such rows.
+// rows naming the same entity form one keyword group, in the order first named; rows
+// without one stand for themselves. each alias remembers its row, e.g. keywords.csv:12
+pub fn load_keyword_list(path: &Path) -> Result<Vec<Entity>, String> {
+    let file = path.to_string_lossy();
+    let name = path
+        .file_name()
+        .map_or(file.to_string(), |name| name.to_string_lossy().to_string());
+    let rows: Vec<(String, Row)> = if path.extension().is_some_and(|extension| extension == "csv") {
+        // comments are skipped here rather than by the reader, which would not count their
+        // lines; rows are numbered by the line they start on, as in a spreadsheet
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|error| format!("{}: {}", file, error))?;
+        let mut headers = None;
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|error| format!("{}: {}", file, error))?;
+            if record.get(0).is_some_and(|field| field.starts_with('#')) {
+                continue;
+            }
+            let Some(headers) = &headers else {
+                headers = Some(record);
+                continue;
+            };
+            let line = record.position().map_or(0, |position| position.line());
+            let row = record
+                .deserialize(Some(headers))
+                .map_err(|error| format!("{}:{}: {}", name, line, error))?;
+            rows.push((format!("{}:{}", name, line), row));
+        }
+        rows
+    } else {
+        // yaml rows are numbered from 1
+        FileSource::new(path)
+            .try_load::<Vec<Row>>()?
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| (format!("{}:{}", name, index + 1), row))
+            .collect()
+    };
+
+    let mut entities: Vec<Entity> = Vec::new();
+    for (id, row) in rows {
+        if row.keyword.is_empty() {
+            return Err(format!("{}: empty keyword", id));
+        }
+        if let (Some(from), Some(to)) = (row.from, row.to) {
+            if from > to {
+                return Err(format!(
+                    "{}: {} is valid to {}, before from ({})",
+                    id, row.keyword, to, from
+                ));
+            }
+        }
+        let alias = Alias {
+            category: row.category.filter(|category| !category.is_empty()),
+            notes: row.notes.filter(|notes| !notes.is_empty()),
+            row: Some(id),
+            ..Alias::new(row.keyword.trim()).dates(row.from, row.to)
+        };
+        let name = match row.entity.filter(|entity| !entity.trim().is_empty()) {
+            Some(entity) => entity.trim().to_string(),
+            None => alias.keyword.clone(),
+        };
+        match entities.iter_mut().find(|entity| entity.name == name) {
+            Some(entity) => entity.aliases.push(alias),
+            None => entities.push(Entity {
+                name,
+                aliases: vec![alias],
+            }),
+        }
+    }
+    Ok(entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // tests run in parallel, so every call writes to a directory of its own
+    static DIRECTORIES: AtomicUsize = AtomicUsize::new(0);
+
+    fn load(name: &str, text: &str) -> Result<Vec<Entity>, String> {
+        let directory = std::env::temp_dir().join(format!(
+            "webb-keywords-{}-{}",
+            std::process::id(),
+            DIRECTORIES.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join(name);
+        std::fs::write(&file, text).unwrap();
+        let entities = load_keyword_list(&file);
+        std::fs::remove_dir_all(&directory).unwrap();
+        entities
+    }
+
+    #[test]
+    fn groups_rows_by_entity() {
+        let entities = load(
+            "keywords.csv",
+            "keyword,entity,category,from,to,notes\n\
+             # renamed in 1988\n\
+             华中工学院, HUST, university, 1952-01-01, 1988-12-31,\"founded 1952, renamed 1988\"\n\
+             同济,,,,,\n\
+             华中理工大学,HUST,university,1988-01-01,,\n",
+        )
+        .unwrap();
+        assert_eq!(entities.len(), 2);
+        let hust = &entities[0].aliases;
+        assert_eq!(hust.len(), 2);
+        assert_eq!(hust[0].keyword, "华中工学院");
+        assert_eq!(hust[0].category.as_deref(), Some("university"));
+        assert_eq!(hust[0].to, Some(Date::new(1988, 12, 31)));
+        assert_eq!(hust[0].notes.as_deref(), Some("founded 1952, renamed 1988"));
+        assert_eq!(hust[0].row.as_deref(), Some("keywords.csv:3"));
+        assert_eq!(hust[1].row.as_deref(), Some("keywords.csv:5"));
+        assert_eq!(entities[1].name, "同济");
+        assert_eq!(entities[1].aliases[0].category, None);
+
+        let entities = load(
+            "keywords.yaml",
+            "- {keyword: 同济医学院, entity: Tongji Medical, category: medical school}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            entities[0].aliases[0].row.as_deref(),
+            Some("keywords.yaml:1")
+        );
+        let example = load("keywords.csv", include_str!("../keywords.csv")).unwrap();
+        assert_eq!(example[1].aliases[0].row.as_deref(), Some("keywords.csv:8"));
+        assert_eq!(
+            load("keywords.csv", "keyword,entity\n,HUST\n"),
+            Err("keywords.csv:2: empty keyword".to_string())
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{links::Url, parsers::*, Error, SuccessTarget, TargetType};
+
+// something found on a page: either another page to crawl or an article to examine
+pub enum Discovered {
+    Page(Url),
+    Article(SuccessTarget),
+}
+
+// a crawl level is a parser for its pages plus the level of the pages they link to
+pub struct Level {
+    pub target_type: TargetType,
+    pub name: &'static str,
+    pub parse: fn(String, &Url, &Selectors) -> Result<Parsed<Discovered>, Error>,
+    pub child: Option<&'static Level>,
+}
+
+pub static INDEX: Level = Level {
+    target_type: TargetType::Index,
+    name: "index",
+    parse: |content, url, selectors| pages(parse_index_page(content, url, &selectors.index)),
+    child: Some(&YEAR),
+};
+
+pub static YEAR: Level = Level {
+    target_type: TargetType::Year,
+    name: "year",
+    parse: |content, url, selectors| pages(parse_year_page(content, url, &selectors.year)),
+    child: Some(&MONTH),
+};
+
+pub static MONTH: Level = Level {
+    target_type: TargetType::Month,
+    name: "month",
+    parse: |content, url, selectors| pages(parse_month_page(content, url, &selectors.month)),
+    child: Some(&DAY),
+};
+
+pub static DAY: Level = Level {
+    target_type: TargetType::Day,
+    name: "day",
+    parse: |content, url, selectors| pages(parse_day_page(content, url, &selectors.day)),
+    child: Some(&PAGE),
+};
+
+pub static PAGE: Level = Level {
+    target_type: TargetType::Page,
+    name: "page",
+    parse: |content, url, selectors| articles(parse_page_page(content, url, &selectors.page)),
+    child: None,
+};
+
+fn pages(parsed: Result<Parsed<Url>, Error>) -> Result<Parsed<Discovered>, Error> {
+    parsed.map(|urls| urls.map(Discovered::Page))
+}
+
+fn articles(parsed: Result<Parsed<SuccessTarget>, Error>) -> Result<Parsed<Discovered>, Error> {
+    parsed.map(|targets| targets.map(Discovered::Article))
+}
@@ -0,0 +1,805 @@
+pub const ROOT: &str = "https://cn.govopendata.com/renminribao/";
+pub const THROTTLE: usize = 2;
+const RETRIES: u32 = 5;
+const TIMEOUT: Duration = Duration::from_secs(30);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+use log::{info, trace, warn};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{atomic::AtomicBool, atomic::AtomicU64, atomic::Ordering, Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+mod log_expect;
+pub use log_expect::LogExpect;
+
+mod file_source;
+pub use file_source::FileSource;
+
+mod control;
+pub use control::{Command, Control, ControlState};
+
+mod fetch;
+use fetch::FetchClient;
+
+mod entity;
+pub use entity::{Alias, Entity};
+
+mod keyword_list;
+pub use keyword_list::load_keyword_list;
+
+mod normalize;
+
+mod matcher;
+pub use matcher::{Match, Matcher, Occurrence};
+
+mod logging;
+pub use logging::Logging;
+
+mod parsers;
+pub use parsers::{ParseWarning, Selectors, SiteConfig};
+
+mod job_manager;
+use job_manager::JobManager;
+
+mod links;
+pub use links::Url;
+
+mod level;
+use level::{Discovered, Level};
+
+mod visited;
+pub use visited::Visited;
+
+mod date;
+pub use date::{Date, DateRange};
+
+mod event;
+use event::Emitter;
+pub use event::Event;
+
+mod sink;
+pub use sink::{Sink, YamlSink};
+
+mod report;
+pub use report::Report;
+
+mod metrics;
+pub use metrics::Metrics;
+
+mod dashboard;
+pub use dashboard::Dashboard;
+
+mod profile;
+pub use profile::{Profile, Profiles};
+
+mod crawler;
+pub use crawler::{Crawler, CrawlerBuilder};
+
+// everything a crawl needs to know up front
+pub struct Config {
+    // the archive index, e.g. https://cn.govopendata.com/renminribao/
+    pub root: Url,
+    pub keywords: Vec<String>,
+    // keyword groups, e.g. the former names of an institution, tagging hits with the entity
+    pub entities: Vec<Entity>,
+    // OCR noise in titles and its replacement, e.g. ("＠", ""), applied once titles are normalized
+    pub ocr_substitutions: Vec<(String, String)>,
+    // edits tolerated when a keyword is not found as is, e.g. 1 for one damaged character;
+    // 0 only matches exactly; above 0, each keyword of four characters or more is then tried
+    // against every title not matched exactly, which is slow with long keyword lists
+    pub max_edits: usize,
+    // issue dates to search; None searches the whole archive
+    pub dates: Option<DateRange>,
+    // pages (版面) to search, e.g. [1] for the front page only; empty searches all pages
+    pub pages: Vec<u32>,
+    // skip malformed list items instead of failing the whole page
+    pub lenient: bool,
+    // pause and the number of requests in flight, adjustable while running
+    pub control: Arc<Control>,
+    pub retries: u32,
+    // the wait before the first retry of a page, doubled for each further one
+    pub retry_delay: Duration,
+    pub client: reqwest::Client,
+    // pages already handled, e.g. completed pages loaded from a previous run
+    pub visited: Visited,
+    // hits and ruled out titles of the previous run being resumed, kept ahead of new ones;
+    // failures are not, the pages that failed are requested again
+    pub previous_successes: Vec<SuccessTarget>,
+    pub previous_excluded: Vec<ExcludedTarget>,
+    // receive events and results, e.g. to store them or drive a UI
+    pub sinks: Vec<Box<dyn Sink>>,
+    // keep every examined title, after those of a previous run; None keeps none
+    pub corpus: Option<Vec<ScannedTitle>>,
+    // the markup of the archive, e.g. loaded from site.yaml with Selectors::load
+    pub selectors: Selectors,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            root: Url::parse(ROOT).log_expect("Invalid site root."),
+            keywords: Vec::new(),
+            entities: Vec::new(),
+            ocr_substitutions: Vec::new(),
+            max_edits: 0,
+            dates: None,
+            pages: Vec::new(),
+            lenient: true,
+            control: Control::new(THROTTLE),
+            retries: RETRIES,
+            retry_delay: RETRY_DELAY,
+            client: reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .log_expect("Failed to build HTTP client."),
+            visited: Visited::new(),
+            previous_successes: Vec::new(),
+            previous_excluded: Vec::new(),
+            sinks: Vec::new(),
+            corpus: None,
+            selectors: Selectors::default(),
+        }
+    }
+}
+
+impl Config {
+    // whether a page linked from a listing should be crawled at all
+    fn selects(&self, level: &Level, url: &Url) -> bool {
+        let in_range = self
+            .dates
+            .is_none_or(|range| range.overlaps(&date::date_prefix(&self.root, url)));
+        let on_page = level.target_type != TargetType::Page
+            || self.pages.is_empty()
+            || parsers::page_number(url).is_some_and(|number| self.pages.contains(&number));
+        in_range && on_page
+    }
+}
+
+// what a crawl found, collected once it is finished or stopped
+pub struct CrawlResults {
+    pub successes: Vec<SuccessTarget>,
+    // titles with a keyword that a rule ruled out, to be reviewed
+    pub excluded: Vec<ExcludedTarget>,
+    pub failures: Vec<FailTarget>,
+    pub warnings: Vec<ParseWarning>,
+    pub visited: Visited,
+    // every title examined, if kept, see Config::corpus
+    pub corpus: Vec<ScannedTitle>,
+    // articles examined plus pages that failed
+    pub count: u64,
+    // articles whose title was examined
+    pub articles: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct SuccessTarget {
+    pub title: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<u32>,
+    // the keyword that matched, once examined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
+    // the entity the keyword stands for, e.g. HUST for 华中工学院
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+    // 1.0 for an exact match, lower for a fuzzy one that should be reviewed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    // the category of the alias that matched, e.g. university
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    // the row of the keyword list that matched, e.g. keywords.csv:12
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row: Option<String>,
+}
+
+// an article title as scanned, kept to match it again offline, see rematch
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ScannedTitle {
+    pub title: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<Date>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<u32>,
+}
+
+impl ScannedTitle {
+    fn target(self) -> SuccessTarget {
+        SuccessTarget {
+            title: self.title,
+            url: self.url,
+            page: self.page,
+            page_number: self.page_number,
+            keyword: None,
+            entity: None,
+            confidence: None,
+            category: None,
+            row: None,
+        }
+    }
+}
+
+// an article whose title contained a keyword but was ruled out by one of its rules
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ExcludedTarget {
+    #[serde(flatten)]
+    pub target: SuccessTarget,
+    // e.g. "excluded by 安危同济" or "not followed by 大学/医学院/医科"
+    pub rule: String,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TargetType {
+    Index,
+    Year,
+    Month,
+    Day,
+    Page,
+    Article,
+}
+
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FailCategory {
+    Timeout,
+    Connection,
+    HttpStatus,
+    Decode,
+    Parsing,
+    General,
+}
+
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct FailTarget {
+    pub url: String,
+    pub target_type: TargetType,
+    pub category: FailCategory,
+    pub message: String,
+    pub attempts: u32,
+    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    // the HTTP status of the last response, if there was one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+}
+
+impl FailTarget {
+    fn new(url: &str, target_type: TargetType, error: Error) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .log_expect("Error: Time went backwards. How?")
+            .as_secs();
+        let (category, message, attempts, selector, status) = match error {
+            Error::NetworkError {
+                category,
+                message,
+                attempts,
+                status,
+            } => (category, message, attempts, None, status),
+            Error::ParsingError(selector) => (
+                FailCategory::Parsing,
+                format!("Failed to select {}.", selector),
+                1,
+                Some(selector),
+                None,
+            ),
+            Error::GeneralError => (
+                FailCategory::General,
+                "General error.".to_string(),
+                1,
+                None,
+                None,
+            ),
+        };
+        FailTarget {
+            url: url.to_string(),
+            target_type,
+            category,
+            message,
+            attempts,
+            timestamp,
+            selector,
+            status,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    NetworkError {
+        category: FailCategory,
+        message: String,
+        attempts: u32,
+        status: Option<u16>,
+    },
+    ParsingError(String),
+    GeneralError,
+}
+
+// counters shared with the job manager for progress display
+pub struct Progress {
+    pub count: AtomicU64,
+    pub successes: AtomicU64,
+    pub articles: AtomicU64,
+}
+
+// state of a running crawl, shared by all of its tasks
+struct Context {
+    config: Config,
+    fetch_client: FetchClient,
+    matcher: Matcher,
+    progress: Arc<Progress>,
+    job_manager: Mutex<JobManager>,
+    events: Emitter,
+    done: Arc<Notify>,
+    stopped: AtomicBool,
+    // tasks of scheduled jobs not known to be finished, awaited before the crawl finishes
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    success_list: Mutex<Vec<SuccessTarget>>,
+    excluded_list: Mutex<Vec<ExcludedTarget>>,
+    fail_list: Mutex<Vec<FailTarget>>,
+    warn_list: Mutex<Vec<ParseWarning>>,
+    visited: Mutex<Visited>,
+    corpus: Option<Mutex<Vec<ScannedTitle>>>,
+}
+
+impl Context {
+    fn new(mut config: Config) -> Self {
+        let progress = Arc::new(Progress {
+            count: AtomicU64::new(0),
+            successes: AtomicU64::new(0),
+            articles: AtomicU64::new(0),
+        });
+        let done = Arc::new(Notify::new());
+        let mut visited = std::mem::replace(&mut config.visited, Visited::new());
+        let successes = std::mem::take(&mut config.previous_successes);
+        let excluded = std::mem::take(&mut config.previous_excluded);
+        // articles found before are not examined again, whether their page was completed or not
+        for url in successes
+            .iter()
+            .chain(excluded.iter().map(|excluded| &excluded.target))
+            .map(|target| &target.url)
+        {
+            visited.examine(url);
+        }
+        let events = Emitter::new(std::mem::take(&mut config.sinks));
+        let corpus = config.corpus.take().map(Mutex::new);
+        Context {
+            fetch_client: FetchClient::new(
+                config.client.clone(),
+                Arc::clone(&config.control),
+                config.retries,
+                config.retry_delay,
+                events.clone(),
+            ),
+            job_manager: Mutex::new(JobManager::new(
+                Arc::clone(&done),
+                Arc::clone(&progress),
+                events.clone(),
+            )),
+            matcher: Matcher::new(&config),
+            events,
+            progress,
+            done,
+            stopped: AtomicBool::new(false),
+            tasks: Mutex::new(Vec::new()),
+            success_list: Mutex::new(successes),
+            excluded_list: Mutex::new(excluded),
+            fail_list: Mutex::new(Vec::new()),
+            warn_list: Mutex::new(Vec::new()),
+            visited: Mutex::new(visited),
+            corpus,
+            config,
+        }
+    }
+
+    // the canonical url a page is visited under, e.g. .../1974/11/6/ for .../1974/11/06/
+    fn page_key(&self, url: &Url) -> Url {
+        links::canonicalize(url.clone(), self.config.root.scheme())
+    }
+
+    fn results(&self) -> CrawlResults {
+        fn take<T>(list: &Mutex<Vec<T>>) -> Vec<T> {
+            std::mem::take(&mut *list.lock().log_expect("Failed to aquire lock."))
+        }
+        CrawlResults {
+            successes: take(&self.success_list),
+            excluded: take(&self.excluded_list),
+            failures: take(&self.fail_list),
+            warnings: take(&self.warn_list),
+            visited: std::mem::replace(
+                &mut *self.visited.lock().log_expect("Failed to aquire lock."),
+                Visited::new(),
+            ),
+            corpus: self.corpus.as_ref().map(take).unwrap_or_default(),
+            count: self.progress.count.load(Ordering::Relaxed),
+            articles: self.progress.articles.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// crawl the whole archive below config.root, until every page is handled or stop resolves
+pub async fn crawl(config: Config, stop: impl Future<Output = ()>) -> CrawlResults {
+    let context = Arc::new(Context::new(config));
+    let root = Job::new(context.config.root.clone(), &level::INDEX);
+    schedule(&context, root);
+
+    let stopped = tokio::select! {
+        _ = context.done.notified() => {
+            info!("Job done.");
+            false
+        }
+        _ = stop => {
+            info!("Crawl stopped.");
+            true
+        }
+    };
+    let tasks = {
+        let mut tasks = context.tasks.lock().log_expect("Failed to aquire lock.");
+        context.stopped.store(true, Ordering::Relaxed);
+        std::mem::take(&mut *tasks)
+    };
+    // no job is scheduled from here on; those still running are cut short when stopped,
+    // and waited for either way, so that none of their events follows Event::Finished
+    if stopped {
+        tasks.iter().for_each(JoinHandle::abort);
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    info!(
+        "Processed {}/{}.",
+        context.progress.successes.load(Ordering::Relaxed),
+        context.progress.count.load(Ordering::Relaxed)
+    );
+    finish(&context)
+}
+
+// match the titles of an earlier crawl against the keywords of config, without fetching;
+// only titles within the dates and pages of config are examined. the titles examined are
+// replayed as Event::LevelExpanded of their pages, so that sinks such as the report count them
+pub fn rematch(config: Config, corpus: impl IntoIterator<Item = ScannedTitle>) -> CrawlResults {
+    let context = Context::new(config);
+    let mut pages: BTreeMap<String, usize> = BTreeMap::new();
+    for scanned in corpus {
+        let in_range = context
+            .config
+            .dates
+            .is_none_or(|range| scanned.date.is_some_and(|date| range.contains(date)));
+        let on_page = context.config.pages.is_empty()
+            || scanned
+                .page_number
+                .is_some_and(|number| context.config.pages.contains(&number));
+        if !(in_range && on_page) {
+            continue;
+        }
+        let page = Url::parse(&scanned.url)
+            .map(|url| links::without_fragment(&url).to_string())
+            .unwrap_or_else(|_| scanned.url.clone());
+        let examined = context.progress.articles.load(Ordering::Relaxed);
+        examine(&context, scanned.target());
+        if context.progress.articles.load(Ordering::Relaxed) > examined {
+            *pages.entry(page).or_insert(0) += 1;
+        }
+    }
+    for (url, articles) in pages {
+        context.events.emit(Event::LevelExpanded {
+            url,
+            level: TargetType::Page,
+            pages: 0,
+            articles,
+        });
+    }
+    info!(
+        "Matched {}/{}.",
+        context.progress.successes.load(Ordering::Relaxed),
+        context.progress.count.load(Ordering::Relaxed)
+    );
+    finish(&context)
+}
+
+// hand the results to every sink
+fn finish(context: &Context) -> CrawlResults {
+    let results = context.results();
+    context.events.emit(Event::Finished {
+        successes: results.successes.len(),
+        failures: results.failures.len(),
+        count: results.count,
+    });
+    for sink in context.events.sinks() {
+        sink.finish(&results);
+    }
+    results
+}
+
+// a scheduled unit of work, the level of the page is decided once here
+struct Job {
+    url: Url,
+    level: &'static Level,
+}
+
+impl Job {
+    fn new(url: Url, level: &'static Level) -> Self {
+        Job { url, level }
+    }
+
+    // job for a page linked from this one
+    fn child(&self, url: Url) -> Job {
+        Job::new(
+            url,
+            self.level
+                .child
+                .log_expect("Pages of this level do not link to further pages."),
+        )
+    }
+
+    fn fail(&self, error: Error) -> FailTarget {
+        FailTarget::new(self.url.as_str(), self.level.target_type, error)
+    }
+}
+
+// returns whether the job was new and got scheduled
+fn schedule(context: &Arc<Context>, job: Job) -> bool {
+    // stopping takes this lock too, so no task is spawned once it has taken the tasks
+    let mut tasks = context.tasks.lock().log_expect("Failed to aquire lock.");
+    if context.stopped.load(Ordering::Relaxed) {
+        return false;
+    }
+    {
+        let mut lock = context.visited.lock().log_expect("Failed to aquire lock.");
+        if !lock.visit(&context.page_key(&job.url)) {
+            return false;
+        }
+    }
+    {
+        let mut lock = context
+            .job_manager
+            .lock()
+            .log_expect("Failed to aquire lock.");
+        lock.allocate();
+    }
+    let span = tracing::info_span!("job", level = job.level.name, url = %job.url);
+    tasks.retain(|task| !task.is_finished());
+    tasks.push(tokio::spawn(run(Arc::clone(context), job).instrument(span)));
+    true
+}
+
+fn record_failure(context: &Context, job: &Job, error: Error) {
+    context.progress.count.fetch_add(1, Ordering::Relaxed);
+    let failure = job.fail(error);
+    context.events.emit(Event::PageFailed(failure.clone()));
+    let mut lock = context
+        .fail_list
+        .lock()
+        .log_expect("Failed to aquire lock.");
+    lock.push(failure);
+}
+
+fn finish_job(context: &Context) {
+    let mut lock = context
+        .job_manager
+        .lock()
+        .log_expect("Failed to aquire lock.");
+    lock.deallocate();
+}
+
+// fetch and parse a page of any level, dispatching whatever it lists
+async fn run(context: Arc<Context>, job: Job) {
+    let name = job.level.name;
+    trace!("[{}] Processing {}.", name, job.url);
+    let fetched = context.fetch_client.fetch(job.url.as_str()).await;
+    if let Ok((content, status, elapsed)) = &fetched {
+        context.events.emit(Event::PageFetched {
+            url: job.url.to_string(),
+            level: job.level.target_type,
+            status: *status,
+            bytes: content.len(),
+            elapsed: *elapsed,
+        });
+    }
+    let error = match fetched {
+        Ok((content, _, _)) => {
+            match (job.level.parse)(content, &job.url, &context.config.selectors) {
+                Ok(discovered) if !context.config.lenient && !discovered.warnings.is_empty() => {
+                    let selector = discovered.warnings[0].selector.clone();
+                    warn!("[{}] Parsing {} failed: {}.", name, job.url, selector);
+                    Error::ParsingError(selector)
+                }
+                Ok(discovered) => {
+                    if !discovered.warnings.is_empty() {
+                        let mut lock = context
+                            .warn_list
+                            .lock()
+                            .log_expect("Failed to aquire lock.");
+                        lock.extend(discovered.warnings);
+                    }
+                    let (mut pages, mut articles) = (0, 0);
+                    for each in discovered.items {
+                        match each {
+                            Discovered::Page(url) => {
+                                let child = job.child(url);
+                                if !context.config.selects(child.level, &child.url) {
+                                    continue;
+                                }
+                                trace!("[{}] Spawning task for {}.", name, &child.url);
+                                if schedule(&context, child) {
+                                    pages += 1;
+                                }
+                            }
+                            Discovered::Article(target) => {
+                                articles += 1;
+                                examine(&context, target);
+                            }
+                        }
+                    }
+                    context.events.emit(Event::LevelExpanded {
+                        url: job.url.to_string(),
+                        level: job.level.target_type,
+                        pages,
+                        articles,
+                    });
+                    if job.level.child.is_none() {
+                        let mut lock = context.visited.lock().log_expect("Failed to aquire lock.");
+                        lock.complete(&context.page_key(&job.url));
+                    }
+                    finish_job(&context);
+                    return;
+                }
+                Err(error) => {
+                    warn!("[{}] Parsing {} failed: {:?}.", name, job.url, error);
+                    error
+                }
+            }
+        }
+        Err(error) => {
+            warn!("[{}] Fetching {} failed: {:?}.", name, job.url, error);
+            error
+        }
+    };
+    record_failure(&context, &job, error);
+    finish_job(&context);
+}
+
+// test an article title for keyword presence
+fn examine(context: &Context, mut target: SuccessTarget) {
+    {
+        let mut lock = context.visited.lock().log_expect("Failed to aquire lock.");
+        if !lock.examine(&target.url) {
+            return;
+        }
+    }
+    context.progress.count.fetch_add(1, Ordering::Relaxed);
+    context.progress.articles.fetch_add(1, Ordering::Relaxed);
+    let date = Url::parse(&target.url)
+        .ok()
+        .and_then(|url| Date::from_url(&context.config.root, &url));
+    if let Some(corpus) = &context.corpus {
+        let mut lock = corpus.lock().log_expect("Failed to aquire lock.");
+        lock.push(ScannedTitle {
+            title: target.title.clone(),
+            url: target.url.clone(),
+            date,
+            page: target.page.clone(),
+            page_number: target.page_number,
+        });
+    }
+    let Some(found) = context.matcher.find(&target, date) else {
+        return;
+    };
+    target.keyword = Some(found.alias.keyword.clone());
+    target.entity = Some(found.entity.name.clone());
+    target.confidence = Some(found.confidence);
+    target.category = found.alias.category.clone();
+    target.row = found.alias.row.clone();
+    if let Some(rule) = found.excluded {
+        let excluded = ExcludedTarget { target, rule };
+        context.events.emit(Event::HitExcluded(excluded.clone()));
+        let mut lock = context
+            .excluded_list
+            .lock()
+            .log_expect("[article] Failed to aquire lock.");
+        lock.push(excluded);
+    } else {
+        context.progress.successes.fetch_add(1, Ordering::Relaxed);
+        context.events.emit(Event::HitFound(target.clone()));
+        let mut lock = context
+            .success_list
+            .lock()
+            .log_expect("[article] Failed to aquire lock.");
+        lock.push(target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: [&Level; 5] = [
+        &level::INDEX,
+        &level::YEAR,
+        &level::MONTH,
+        &level::DAY,
+        &level::PAGE,
+    ];
+
+    fn site(path: &str) -> Url {
+        Url::parse(ROOT)
+            .and_then(|root| root.join(path))
+            .log_expect("Invalid test url.")
+    }
+
+    fn network_error() -> Error {
+        Error::NetworkError {
+            category: FailCategory::Timeout,
+            message: "timed out".to_string(),
+            attempts: 5,
+            status: None,
+        }
+    }
+
+    #[test]
+    fn levels_link_to_the_next_level() {
+        let child = |level: &Level| level.child.map(|each| each.target_type);
+        assert_eq!(level::INDEX.target_type, TargetType::Index);
+        assert_eq!(child(&level::INDEX), Some(TargetType::Year));
+        assert_eq!(child(&level::YEAR), Some(TargetType::Month));
+        assert_eq!(child(&level::MONTH), Some(TargetType::Day));
+        assert_eq!(child(&level::DAY), Some(TargetType::Page));
+        assert_eq!(child(&level::PAGE), None);
+    }
+
+    #[test]
+    fn failures_keep_the_scheduled_level() {
+        for level in LEVELS {
+            let job = Job::new(site(""), level);
+            assert_eq!(job.fail(network_error()).target_type, level.target_type);
+            let parsing = Error::ParsingError("link href".to_string());
+            assert_eq!(job.fail(parsing).target_type, level.target_type);
+        }
+    }
+
+    #[test]
+    fn children_are_classified_one_level_down() {
+        let index = Job::new(site(""), &level::INDEX);
+        let year = index.child(site("1974/"));
+        let month = year.child(site("1974/11/"));
+        let day = month.child(site("1974/11/06/"));
+        let page = day.child(site("1974/11/06/4/"));
+        assert_eq!(year.level.target_type, TargetType::Year);
+        assert_eq!(month.level.target_type, TargetType::Month);
+        assert_eq!(day.level.target_type, TargetType::Day);
+        assert_eq!(page.level.target_type, TargetType::Page);
+        assert_eq!(day.fail(network_error()).target_type, TargetType::Day);
+        assert_eq!(page.fail(network_error()).target_type, TargetType::Page);
+    }
+
+    #[test]
+    fn recorded_failures_are_classified_by_job() {
+        let context = Context::new(Config::default());
+        for level in LEVELS {
+            let url = site(&format!("record/{}/", level.name));
+            record_failure(&context, &Job::new(url.clone(), level), network_error());
+        }
+        let results = context.results();
+        for level in LEVELS {
+            let url = site(&format!("record/{}/", level.name));
+            let recorded = results
+                .failures
+                .iter()
+                .find(|each| each.url == url.as_str())
+                .log_expect("Failure was not recorded.");
+            assert_eq!(recorded.target_type, level.target_type);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::Error;
+pub use url::Url;
+
+// the default archive, which parser tests resolve their fixtures against
+#[cfg(test)]
+lazy_static::lazy_static! {
+    pub static ref SITE: Url = Url::parse(crate::ROOT).unwrap();
+}
+
+// resolve a link found on the page at base into an absolute url on the same site, otherwise
+// as the site spelled it; that is the url fetched, canonicalize only tells pages apart
+pub fn resolve(base: &Url, href: &str) -> Result<Url, Error> {
+    let url = base
+        .join(href.trim())
+        .map_err(|_| Error::ParsingError("link href".to_string()))?;
+    if !matches!(url.scheme(), "http" | "https")
+        || url.host_str() != base.host_str()
+        || url.port() != base.port()
+    {
+        return Err(Error::ParsingError("off-site link".to_string()));
+    }
+    Ok(url)
+}
+
+// one spelling per page: the site's scheme, no query, no zero-padded numbers, trailing slash;
+// the key under which a page counts as visited, whichever of its spellings was linked
+pub fn canonicalize(mut url: Url, scheme: &str) -> Url {
+    let _ = url.set_scheme(scheme);
+    url.set_query(None);
+    let segments = url
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| {
+                    if segment.len() > 1 && segment.bytes().all(|byte| byte.is_ascii_digit()) {
+                        let trimmed = segment.trim_start_matches('0');
+                        if trimmed.is_empty() { "0" } else { trimmed }.to_string()
+                    } else {
+                        segment.to_string()
+                    }
+                })
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+    let mut path = format!("/{}", segments.join("/"));
+    let is_file = segments.last().is_some_and(|last| last.contains('.'));
+    if !segments.is_empty() && !is_file {
+        path.push('/');
+    }
+    url.set_path(&path);
+    url
+}
+
+// the same url without the #fragment, i.e. the page an article is printed on
+pub fn without_fragment(url: &Url) -> Url {
+    let mut page = url.clone();
+    page.set_fragment(None);
+    page
+}
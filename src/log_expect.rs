@@ -2,6 +2,14 @@ use std::process::abort;
 
 use log::error;
 
+// the log may be written by a background thread that abort stops before it gets to the
+// message, so it also goes straight to stderr
+fn fail(message: &str) -> ! {
+    error!("{}", message);
+    eprintln!("error: {}", message);
+    abort();
+}
+
 pub trait LogExpect<T> {
     fn log_expect(self, msg: &str) -> T;
 }
@@ -10,10 +18,7 @@ impl<T, E> LogExpect<T> for Result<T, E> {
     fn log_expect(self, message: &str) -> T {
         match self {
             Ok(inner) => inner,
-            Err(_error) => {
-                error!("{}", message);
-                abort();
-            }
+            Err(_error) => fail(message),
         }
     }
 }
@@ -22,10 +27,7 @@ impl<T> LogExpect<T> for Option<T> {
     fn log_expect(self, msg: &str) -> T {
         match self {
             Some(inner) => inner,
-            None => {
-                error!("{}", msg);
-                abort();
-            }
+            None => fail(msg),
         }
     }
 }
@@ -0,0 +1,91 @@
+use crate::LogExpect;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+// module paths of the layers with their own verbosity
+const FETCH_TARGET: &str = "hust_news_entry::fetch";
+const MATCHING_TARGET: &str = "hust_news_entry::matcher";
+
+// where and how much to log; RUST_LOG, if set, overrides the levels
+pub struct Logging {
+    // e.g. "info" or "debug"
+    pub level: String,
+    // requests, retries and their failures
+    pub fetch_level: String,
+    // keywords found in titles
+    pub matching_level: String,
+    // one JSON object per line instead of human readable lines
+    pub json: bool,
+    // also log to stderr; off while the dashboard owns the terminal
+    pub stderr: bool,
+    // directory of the daily rotated log file, None to not log to a file
+    pub directory: Option<PathBuf>,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Logging {
+            level: "info".to_string(),
+            fetch_level: "warn".to_string(),
+            matching_level: "info".to_string(),
+            json: false,
+            stderr: true,
+            directory: None,
+        }
+    }
+}
+
+type BoxedLayer = Box<dyn Layer<tracing_subscriber::Registry> + Send + Sync>;
+
+impl Logging {
+    fn filter(&self) -> EnvFilter {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+            EnvFilter::new(format!(
+                "{},{}={},{}={}",
+                self.level, FETCH_TARGET, self.fetch_level, MATCHING_TARGET, self.matching_level
+            ))
+        })
+    }
+
+    fn layer<W>(&self, writer: W, ansi: bool) -> BoxedLayer
+    where
+        W: for<'writer> fmt::MakeWriter<'writer> + Send + Sync + 'static,
+    {
+        let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+        if self.json {
+            layer
+                .json()
+                .with_current_span(true)
+                .with_span_list(true)
+                .with_filter(self.filter())
+                .boxed()
+        } else {
+            layer.with_filter(self.filter()).boxed()
+        }
+    }
+
+    // install the logger, log records of the log crate included; keep the guard
+    // alive until exit so buffered lines reach the log file
+    pub fn init(&self) -> Option<WorkerGuard> {
+        let mut layers: Vec<BoxedLayer> = Vec::new();
+        if self.stderr {
+            // plain lines when redirected, e.g. to a file under cron
+            let ansi = !self.json && std::io::stderr().is_terminal();
+            layers.push(self.layer(std::io::stderr, ansi));
+        }
+        let guard = self.directory.as_ref().map(|directory| {
+            let (writer, guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(
+                directory, "webb.log",
+            ));
+            layers.push(self.layer(writer, false));
+            guard
+        });
+        tracing_subscriber::registry()
+            .with(layers)
+            .try_init()
+            .log_expect("Failed to install the logger.");
+        guard
+    }
+}
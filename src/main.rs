@@ -1,350 +1,549 @@
-const TARGETS: [&str; 5] = [
-    "华中工学院",
-    "华中科技大学",
-    "华中理工大学",
-    "同济",
-    "武汉医学院",
+// defaults of every crawl, overridden by the profile file and then by command line flags
+// keyword groups: hits are tagged with the entity, whichever of its names matched
+const ENTITIES: [(&str, &[&str]); 2] = [
+    ("HUST", &["华中工学院", "华中理工大学", "华中科技大学"]),
+    (
+        "Tongji Medical",
+        &["同济医学院", "武汉医学院", "同济医科大学"],
+    ),
 ];
-pub const THROTTLE: usize = 2;
-const ROOT: &str = "https://cn.govopendata.com";
-
-use lazy_static::lazy_static;
-use log::{info, trace, warn};
-use std::sync::{atomic::AtomicU64, atomic::Ordering, mpsc::channel, Mutex};
-
-mod log_expect;
-use log_expect::LogExpect;
-
-mod file_source;
-use file_source::FileSource;
-
-mod fetch;
-use fetch::FetchClient;
-
-mod parsers;
-use parsers::*;
+// plain keywords, each standing for itself; tried after the groups
+const TARGETS: [&str; 1] = ["同济"];
+// default keywords that only count right before one of these words; titles ruled out this
+// way are listed in EXCLUDED_FILE. None by default, a short list drops true hits such as
+// 同济医院, see the front-page profile of webb.yaml for a rule on 同济
+const FOLLOWED_BY: [(&str, &[&str]); 0] = [];
+const EXCLUDED_FILE: &str = "excluded.yaml";
+// OCR noise in titles and its replacement; titles are also normalized to half-width,
+// simplified characters without whitespace before matching
+const OCR_SUBSTITUTIONS: [(&str, &str); 2] = [("＠", ""), ("□", "")];
+// edits tolerated when a keyword is not found as is, e.g. 1 for one damaged character;
+// such hits carry a confidence below 1 and are also listed in REVIEW_FILE
+const MAX_EDITS: usize = 0;
+const REVIEW_FILE: &str = "review.yaml";
+// pages (版面) to search, e.g. [1] for the front page only; empty searches all pages
+const PAGES: &[u32] = &[];
+// skip malformed list items instead of failing the whole page
+const LENIENT: bool = true;
+// skip pages completed by a previous run, as recorded in VISITED_FILE, keeping the hits
+// it stored in SUCCESS_FILE and EXCLUDED_FILE
+const RESUME: bool = false;
+const VISITED_FILE: &str = "visited.yaml";
+const SUCCESS_FILE: &str = "success.yaml";
+// keep every scanned title with its url and date in CORPUS_FILE, so that `webb rematch`
+// can match a new keyword set without crawling again; resumed runs add to it
+const CORPUS: bool = false;
+const CORPUS_FILE: &str = "corpus.yaml";
+// results and report of a rematch are named after those of a crawl with this prefix,
+// e.g. rematch-success.yaml, and never replace them
+const REMATCH_PREFIX: &str = "rematch-";
+// requests started per second, e.g. Some(2.0); None is unlimited
+const RATE_LIMIT: Option<f64> = None;
+// local address taking pause, resume, concurrency <n> and rate <n|off>, one per line,
+// e.g. Some("127.0.0.1:7878"); anyone able to connect can steer the crawl
+const CONTROL_ADDRESS: Option<&str> = None;
+// local address serving Prometheus metrics, e.g. Some("127.0.0.1:9898")
+const METRICS_ADDRESS: Option<&str> = None;
+// run report written as REPORT_FILE.md, .html and .svg
+const REPORT_FILE: &str = "report";
+// draw a live dashboard instead of the terminal title when run in a terminal; the log then
+// only goes to LOG_DIRECTORY
+const DASHBOARD: bool = true;
+// log verbosity of everything, of fetching and of keyword matching; RUST_LOG overrides them
+const LOG_LEVEL: &str = "info";
+const FETCH_LOG_LEVEL: &str = "warn";
+const MATCHING_LOG_LEVEL: &str = "info";
+// log one JSON object per line
+const LOG_JSON: bool = false;
+// directory of the daily rotated webb.log, below the output directory
+const LOG_DIRECTORY: &str = "logs";
+// css selectors of the archive markup, read when it exists; left out ones are built in
+const SITE_FILE: &str = "site.yaml";
+// named crawl profiles, YAML or TOML
+const PROFILE_FILE: &str = "webb.yaml";
+// addresses set to this in a profile turn the endpoint off
+const DISABLED: &str = "off";
+
+use clap::{Args, Parser, Subcommand};
+use hust_news_entry::{
+    Crawler, CrawlerBuilder, Dashboard, Date, DateRange, Entity, Event, ExcludedTarget,
+    FailCategory, FileSource, LogExpect, Logging, Metrics, Profile, Profiles, Report, ScannedTitle,
+    Selectors, Sink, SuccessTarget, Url, Visited, YamlSink, ROOT, THROTTLE,
+};
+use log::{error, info, warn};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+#[derive(Parser)]
+#[command(
+    name = "webb",
+    version,
+    about = "Searches the archive of the People's Daily for keywords."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
 
-mod job_manager;
-use job_manager::JobManager;
+#[derive(Subcommand)]
+enum Command {
+    /// Crawl the archive, e.g. `webb crawl --profile hust-history`
+    Crawl(CrawlArgs),
+    /// Match the titles kept by `webb crawl --corpus` again, without fetching, e.g.
+    /// `webb rematch --profile hust-history --keyword 同济`
+    Rematch(RematchArgs),
+}
 
-pub static COUNT: AtomicU64 = AtomicU64::new(0);
-pub static SUCCESSES: AtomicU64 = AtomicU64::new(0);
+#[derive(Args)]
+struct RematchArgs {
+    /// Corpus to match [default: corpus.yaml in the output directory]
+    #[arg(value_name = "CORPUS")]
+    corpus_file: Option<PathBuf>,
+    // the flags of a crawl; those of fetching are ignored
+    #[command(flatten)]
+    crawl: CrawlArgs,
+}
 
-lazy_static! {
-    static ref SUCCESSLIST: Mutex<Vec<SuccessTarget>> = Mutex::new(Vec::new());
-    static ref FAILLIST: Mutex<Vec<FailTarget>> = Mutex::new(Vec::new());
-    static ref JOBMANAGER: Mutex<JobManager> = Mutex::new(JobManager::new());
-    static ref FETCHCLIENT: FetchClient = FetchClient::new(THROTTLE);
+#[derive(Args)]
+struct CrawlArgs {
+    /// Profile to run from the profile file
+    #[arg(long)]
+    profile: Option<String>,
+    /// Profile file, YAML or TOML
+    #[arg(long, default_value = PROFILE_FILE)]
+    config: PathBuf,
+    /// Keyword to search for, repeatable; replaces the keywords and keyword groups of the profile
+    #[arg(long = "keyword", value_name = "KEYWORD")]
+    keywords: Vec<String>,
+    /// Keyword list, CSV or YAML, with the entity, category, dates and notes of each keyword
+    #[arg(long)]
+    keyword_file: Option<PathBuf>,
+    /// First issue date to search, e.g. 1952-01-01
+    #[arg(long)]
+    from: Option<Date>,
+    /// Last issue date to search, e.g. 1966-12-31; 1966 stands for its last day
+    #[arg(long, value_parser = Date::parse_last)]
+    to: Option<Date>,
+    /// Pages (版面) to search, e.g. 1,2
+    #[arg(long, value_delimiter = ',')]
+    pages: Vec<u32>,
+    /// Requests in flight at once
+    #[arg(long)]
+    concurrency: Option<usize>,
+    /// Attempts per page
+    #[arg(long)]
+    retries: Option<u32>,
+    /// Requests started per second
+    #[arg(long)]
+    rate_limit: Option<f64>,
+    /// Edits tolerated when a keyword is not found as is, e.g. 1; slow with many keywords
+    #[arg(long)]
+    max_edits: Option<usize>,
+    /// Fail pages with malformed list items instead of skipping the items
+    #[arg(long)]
+    strict: bool,
+    /// Skip pages completed by the previous run
+    #[arg(long)]
+    resume: bool,
+    /// Keep every scanned title for `webb rematch`
+    #[arg(long)]
+    corpus: bool,
+    /// Directory of results, report, visited pages and logs
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// CSS selectors of the archive markup, YAML or TOML [default: site.yaml if it exists]
+    #[arg(long)]
+    site: Option<PathBuf>,
+    /// Log to the terminal instead of drawing the dashboard
+    #[arg(long)]
+    no_dashboard: bool,
+    /// Log one JSON object per line
+    #[arg(long)]
+    log_json: bool,
 }
 
-fn main() {
-    pretty_env_logger::init();
+impl CrawlArgs {
+    // the flags given, as the topmost profile layer
+    fn overrides(&self) -> Profile {
+        Profile {
+            keywords: (!self.keywords.is_empty()).then(|| self.keywords.clone()),
+            entities: (!self.keywords.is_empty()).then(Vec::new),
+            keyword_file: self.keyword_file.clone(),
+            from: self.from,
+            to: self.to,
+            pages: (!self.pages.is_empty()).then(|| self.pages.clone()),
+            lenient: self.strict.then_some(false),
+            concurrency: self.concurrency,
+            retries: self.retries,
+            max_edits: self.max_edits,
+            rate_limit: self.rate_limit,
+            resume: self.resume.then_some(true),
+            corpus: self.corpus.then_some(true),
+            output: self.output.clone(),
+            site: self.site.clone(),
+            dashboard: self.no_dashboard.then_some(false),
+            log_json: self.log_json.then_some(true),
+            ..Profile::default()
+        }
+    }
 
-    let (tx, rx) = channel();
-    let tx_clone = tx.clone();
+    // profile file defaults, the named profile and the flags, in that order
+    fn settings(&self) -> Result<Profile, String> {
+        let source = FileSource::new(&self.config);
+        let profiles = if source.exists() {
+            Profiles::load(&source)?
+        } else if self.profile.is_some() {
+            return Err(format!("{}: no such file", self.config.to_string_lossy()));
+        } else {
+            Profiles::default()
+        };
+        let overrides = self.overrides().read_keyword_file(Path::new("."), "")?;
+        overrides.validate("")?;
+        let settings = profiles.profile(self.profile.as_deref())?.merge(overrides);
+        settings.validate("")?;
+        Ok(settings)
+    }
+}
 
-    {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.init(tx_clone);
+// shows progress in the terminal title
+struct TitleSink;
+
+impl Sink for TitleSink {
+    fn event(&self, event: &Event) {
+        if let Event::Progress {
+            running,
+            successes,
+            count,
+        } = event
+        {
+            let _ = crossterm::execute!(
+                std::io::stdout(),
+                crossterm::terminal::SetTitle(format!(
+                    "[webb] Scraping: {} task(s) running. [{}/{}]",
+                    running, successes, count
+                ))
+            );
+        }
     }
+}
 
-    ctrlc::set_handler(move || {
-        info!("Received Ctrl-C event.");
+fn request_stop(tx: &Mutex<Option<oneshot::Sender<()>>>) {
+    if let Some(tx) = tx.lock().log_expect("Failed to aquire lock.").take() {
         tx.send(()).log_expect("Could not send termination signal.");
-    })
-    .log_expect("Failed to set Ctrl-C handler.");
+    }
+}
 
-    let runtime = tokio::runtime::Runtime::new().log_expect("Failed to build Tokio runtime.");
+// an address from the settings, or the default; "off" turns it off
+fn address(setting: &Option<String>, default: Option<&str>) -> Option<String> {
+    match setting.as_deref() {
+        Some(DISABLED) => None,
+        Some(address) => Some(address.to_string()),
+        None => default.map(str::to_string),
+    }
+}
 
+// ENTITIES, then TARGETS standing for themselves, with the rules of FOLLOWED_BY
+fn default_entities() -> Vec<Entity> {
+    let mut entities: Vec<Entity> = ENTITIES
+        .iter()
+        .map(|(name, aliases)| Entity::new(*name, aliases.iter().copied()))
+        .chain(TARGETS.iter().map(|keyword| Entity::keyword(keyword)))
+        .collect();
+    for alias in entities
+        .iter_mut()
+        .flat_map(|entity| entity.aliases.iter_mut())
     {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.allocate();
+        if let Some((_, words)) = FOLLOWED_BY
+            .iter()
+            .find(|(keyword, _)| *keyword == alias.keyword)
+        {
+            alias.followed_by = words.iter().map(|word| word.to_string()).collect();
+        }
     }
+    entities
+}
 
-    runtime.spawn(index_task(
-        "https://cn.govopendata.com/renminribao/".to_string(),
-    ));
-
-    info!("Waiting for Ctrl-C.");
-    rx.recv().log_expect("Failed to listen for event.");
-
-    runtime.shutdown_background();
-
-    info!(
-        "Processed {}/{}:",
-        SUCCESSES.load(Ordering::Relaxed),
-        COUNT.load(Ordering::Relaxed)
-    );
-
-    let success_list_content = SUCCESSLIST
-        .lock()
-        .log_expect("Failed to get data for SUCCESSLIST.");
-
-    let fail_list_content = FAILLIST
-        .lock()
-        .log_expect("Failed to get data for FAILLIST.");
+// the keywords, dates and pages of the settings, with the report in output named prefix
+// followed by REPORT_FILE
+fn matching(settings: &Profile, output: &Path, prefix: &str) -> CrawlerBuilder {
+    // a profile naming keywords, keyword groups or a keyword file replaces both defaults
+    let (keywords, entities) = match (settings.keywords.clone(), settings.entities.clone()) {
+        (None, None) => (Vec::new(), default_entities()),
+        (keywords, entities) => (keywords.unwrap_or_default(), entities.unwrap_or_default()),
+    };
+    let mut builder = Crawler::builder()
+        .keywords(keywords)
+        .entities(entities)
+        .max_edits(settings.max_edits.unwrap_or(MAX_EDITS))
+        .ocr_substitutions(match settings.ocr_substitutions.clone() {
+            Some(substitutions) => substitutions.into_iter().collect::<Vec<(String, String)>>(),
+            None => OCR_SUBSTITUTIONS
+                .iter()
+                .map(|(noise, replacement)| (noise.to_string(), replacement.to_string()))
+                .collect(),
+        })
+        .pages(settings.pages.clone().unwrap_or(PAGES.to_vec()))
+        .sink(Report::new(
+            Url::parse(ROOT).log_expect("Invalid site root."),
+            output.join(format!("{}{}", prefix, REPORT_FILE)),
+        ));
+    if settings.from.is_some() || settings.to.is_some() {
+        builder = builder.date_range(DateRange::new(
+            settings.from.unwrap_or(Date::new(0, 1, 1)),
+            settings.to.unwrap_or(Date::new(9999, 12, 31)),
+        ));
+    }
+    builder
+}
 
-    info!("{:#?}", success_list_content);
-    warn!("{:#?}", fail_list_content);
+// success.yaml and the other result lists in output
+fn result_files(output: &Path) -> YamlSink {
+    let in_output = |file: &str| output.join(file).to_string_lossy().into_owned();
+    YamlSink::new(
+        &in_output(SUCCESS_FILE),
+        &in_output("fail.yaml"),
+        &in_output("warnings.yaml"),
+    )
+    .review(&in_output(REVIEW_FILE))
+    .excluded(&in_output(EXCLUDED_FILE))
+}
 
-    let success_list = FileSource::new("success.yaml");
-    let fail_list = FileSource::new("fail.yaml");
+// the hits of a rematch, next to the results of the crawl without replacing them,
+// e.g. rematch-success.yaml
+fn rematch_files(output: &Path) -> YamlSink {
+    let in_output = |file: &str| {
+        output
+            .join(format!("{}{}", REMATCH_PREFIX, file))
+            .to_string_lossy()
+            .into_owned()
+    };
+    YamlSink::hits(&in_output("success.yaml"))
+        .review(&in_output(REVIEW_FILE))
+        .excluded(&in_output(EXCLUDED_FILE))
+}
 
-    success_list.store(&*success_list_content);
-    fail_list.store(&*fail_list_content);
-    info!("File saved.");
+fn logging(settings: &Profile, stderr: bool, output: &Path) -> Logging {
+    Logging {
+        level: settings.log_level.clone().unwrap_or(LOG_LEVEL.to_string()),
+        fetch_level: settings
+            .fetch_log_level
+            .clone()
+            .unwrap_or(FETCH_LOG_LEVEL.to_string()),
+        matching_level: settings
+            .matching_log_level
+            .clone()
+            .unwrap_or(MATCHING_LOG_LEVEL.to_string()),
+        json: settings.log_json.unwrap_or(LOG_JSON),
+        stderr,
+        directory: Some(output.join(LOG_DIRECTORY)),
+    }
 }
 
-#[derive(serde::Serialize, Debug)]
-pub struct SuccessTarget {
-    title: String,
-    url: String,
+fn main() {
+    let Cli { command } = Cli::parse();
+    let (settings, corpus) = match &command {
+        Command::Crawl(args) => (args.settings(), None),
+        Command::Rematch(args) => (args.crawl.settings(), args.corpus_file.clone()),
+    };
+    let exit = |error: String| -> ! {
+        eprintln!("error: {}", error);
+        std::process::exit(2);
+    };
+    let settings = settings.unwrap_or_else(|error| exit(error));
+    match command {
+        Command::Crawl(_) => {
+            let selectors = selectors(&settings).unwrap_or_else(|error| exit(error));
+            crawl(settings, selectors)
+        }
+        Command::Rematch(_) => rematch(settings, corpus),
+    }
 }
 
-#[derive(serde::Serialize, Debug)]
-enum TargetType {
-    Index,
-    Year,
-    Month,
-    Day,
-    Article,
+// the selectors of the site file named by the settings, else of SITE_FILE if there is one,
+// else the built-in ones
+fn selectors(settings: &Profile) -> Result<Selectors, String> {
+    match &settings.site {
+        Some(site) => Selectors::load(site),
+        None if Path::new(SITE_FILE).exists() => Selectors::load(Path::new(SITE_FILE)),
+        None => Ok(Selectors::default()),
+    }
 }
 
-#[derive(serde::Serialize, Debug)]
-struct FailTarget {
-    url: String,
-    target_type: TargetType,
+fn output_directory(settings: &Profile) -> PathBuf {
+    let output = settings
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+    std::fs::create_dir_all(&output).log_expect("Failed to create the output directory.");
+    output
 }
 
-#[derive(Debug)]
-pub enum Error {
-    NetworkError,
-    ParsingError(String),
-    GeneralError,
+fn rematch(settings: Profile, corpus: Option<PathBuf>) {
+    let output = output_directory(&settings);
+    let _log_guard = logging(&settings, true, &output).init();
+    let corpus_file = corpus.unwrap_or_else(|| output.join(CORPUS_FILE));
+    let Some(corpus) = FileSource::new(&corpus_file).load::<Vec<ScannedTitle>>() else {
+        error!(
+            "No corpus in {}, crawl with --corpus first.",
+            corpus_file.to_string_lossy()
+        );
+        std::process::exit(1);
+    };
+    let results = matching(&settings, &output, REMATCH_PREFIX)
+        .sink(rematch_files(&output))
+        .build()
+        .rematch(corpus);
+    info!(
+        "Found {} hit(s) in {} title(s), {} ruled out.",
+        results.successes.len(),
+        results.articles,
+        results.excluded.len()
+    );
 }
 
-// dispatch year tasks
-async fn index_task(url: String) {
-    match FETCHCLIENT.fetch(&url).await {
-        Ok(content) => match parse_index_page(content) {
-            Ok(years) => {
-                for year in years {
-                    trace!("[index] Spawning task for {}.", &year);
-                    {
-                        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                        lock.allocate();
-                    }
-
-                    tokio::spawn(year_task(year));
-                }
-                {
-                    let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                    lock.deallocate();
-                }
-                return;
-            }
-            Err(error) => {
-                warn!("[index] Parsing {} failed: {:?}.", url, error);
-            }
-        },
-        Err(error) => {
-            warn!("[index] Fetching {} failed: {:?}.", url, error);
-        }
-    }
-    COUNT.fetch_add(1, Ordering::Relaxed);
-    {
-        let mut lock = FAILLIST.lock().log_expect("[index] Failed to aquire lock.");
-        lock.push(FailTarget {
-            url: url.to_string(),
-            target_type: TargetType::Index,
-        });
+fn crawl(settings: Profile, selectors: Selectors) {
+    let output = output_directory(&settings);
+    let in_output = |file: &str| output.join(file);
+    let terminal = settings
+        .dashboard
+        .unwrap_or(DASHBOARD)
+        .then(Dashboard::enter_terminal);
+    let dashboard_enabled = matches!(terminal, Some(Ok(())));
+
+    let _log_guard = logging(&settings, !dashboard_enabled, &output).init();
+    if let Some(Err(error)) = terminal {
+        warn!("No dashboard ({}), logging to stderr instead.", error);
     }
-    {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.deallocate();
+
+    let (tx, rx) = oneshot::channel();
+    let tx = Arc::new(Mutex::new(Some(tx)));
+
+    ctrlc::set_handler({
+        let tx = Arc::clone(&tx);
+        move || {
+            info!("Received Ctrl-C event.");
+            request_stop(&tx);
+        }
+    })
+    .log_expect("Failed to set Ctrl-C handler.");
+
+    let visited_file = in_output(VISITED_FILE);
+    let corpus_file = in_output(CORPUS_FILE);
+    let resume = settings.resume.unwrap_or(RESUME);
+    let mut visited = Visited::new();
+    let mut previous_successes = Vec::new();
+    let mut previous_excluded = Vec::new();
+    if resume {
+        visited.load(&FileSource::new(&visited_file));
+        previous_successes = FileSource::new(in_output(SUCCESS_FILE))
+            .load::<Vec<SuccessTarget>>()
+            .unwrap_or_default();
+        previous_excluded = FileSource::new(in_output(EXCLUDED_FILE))
+            .load::<Vec<ExcludedTarget>>()
+            .unwrap_or_default();
+        info!(
+            "Resuming with {} hit(s) and {} ruled out title(s).",
+            previous_successes.len(),
+            previous_excluded.len()
+        );
     }
-}
 
-// dispatch month tasks
-async fn year_task(url: String) {
-    match FETCHCLIENT.fetch(&url).await {
-        Ok(content) => match parse_year_page(content) {
-            Ok(months) => {
-                for month in months {
-                    trace!("[year] Spawning task for {}.", &month);
-                    {
-                        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                        lock.allocate();
-                    }
-
-                    tokio::spawn(month_task(month));
-                }
-                {
-                    let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                    lock.deallocate();
-                }
-                return;
-            }
-            Err(error) => {
-                warn!("[year] Parsing {} failed: {:?}.", url, error);
-            }
-        },
-        Err(error) => {
-            warn!("[year] Fetching {} failed: {:?}.", url, error);
+    let mut builder = matching(&settings, &output, "")
+        .lenient(settings.lenient.unwrap_or(LENIENT))
+        .concurrency(settings.concurrency.unwrap_or(THROTTLE))
+        .rate_limit(settings.rate_limit.or(RATE_LIMIT))
+        .resume(visited)
+        .previous_hits(previous_successes, previous_excluded)
+        .selectors(selectors)
+        .sink(
+            result_files(&output)
+                .visited(&visited_file.to_string_lossy())
+                .corpus(&corpus_file.to_string_lossy()),
+        );
+    if settings.corpus.unwrap_or(CORPUS) {
+        let mut previous = Vec::new();
+        if resume {
+            previous = FileSource::new(&corpus_file)
+                .load::<Vec<ScannedTitle>>()
+                .unwrap_or_default();
         }
+        builder = builder.corpus(previous);
     }
-    COUNT.fetch_add(1, Ordering::Relaxed);
-    {
-        let mut lock = FAILLIST.lock().log_expect("[year] Failed to aquire lock.");
-        lock.push(FailTarget {
-            url: url.to_string(),
-            target_type: TargetType::Year,
-        });
+    if let Some(retries) = settings.retries {
+        builder = builder.retries(retries);
     }
-    {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.deallocate();
+    let metrics_address = address(&settings.metrics_address, METRICS_ADDRESS);
+    let metrics = Metrics::new();
+    if metrics_address.is_some() {
+        builder = builder.sink(metrics.clone());
     }
-}
+    let mut dashboard = None;
+    if dashboard_enabled {
+        let view = Dashboard::new(builder.control());
+        builder = builder.sink(view.clone());
+        dashboard = Some(view.spawn(move || request_stop(&tx)));
+    } else {
+        builder = builder.sink(TitleSink);
+    }
+    let crawler = builder.build();
 
-// dispatch day tasks
-async fn month_task(url: String) {
-    match FETCHCLIENT.fetch(&url).await {
-        Ok(content) => match parse_month_page(content) {
-            Ok(days) => {
-                for day in days {
-                    trace!("[month] Spawning task for {}.", &day);
-                    {
-                        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                        lock.allocate();
-                    }
-
-                    tokio::spawn(day_task(day));
-                }
-                {
-                    let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                    lock.deallocate();
-                }
-                return;
-            }
-            Err(error) => {
-                warn!("[month] Parsing {} failed: {:?}.", url, error);
+    let runtime = tokio::runtime::Runtime::new().log_expect("Failed to build Tokio runtime.");
+
+    if let Some(address) = address(&settings.control_address, CONTROL_ADDRESS) {
+        match runtime.block_on(tokio::net::TcpListener::bind(&address)) {
+            Ok(listener) => {
+                info!("Listening for control commands on {}.", address);
+                runtime.spawn(crawler.control().serve(listener));
             }
-        },
-        Err(error) => {
-            warn!("[month] Fetching {} failed: {:?}.", url, error);
+            Err(error) => warn!("Control socket on {} unavailable: {}.", address, error),
         }
     }
-    COUNT.fetch_add(1, Ordering::Relaxed);
-    {
-        let mut lock = FAILLIST.lock().log_expect("[month] Failed to aquire lock.");
-        lock.push(FailTarget {
-            url: url.to_string(),
-            target_type: TargetType::Month,
-        });
-    }
-    {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.deallocate();
-    }
-}
 
-// dispatch article tasks
-/* async fn day_task(url: String) {
-    match FETCHCLIENT.fetch(&url).await {
-        Ok(content) => match parse_day_page(content) {
-            Ok(articles) => {
-                for article in articles {
-                    trace!("[day] Spawning task for {}.", &article);
-                    {
-                        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                        lock.allocate();
-                    }
-                    tokio::spawn(article_task(article));
-                }
-                {
-                    let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                    lock.deallocate();
-                }
-                return;
-            }
-            Err(error) => {
-                warn!("[day] Parsing {} failed: {:?}.", url, error);
+    if let Some(address) = metrics_address {
+        match runtime.block_on(tokio::net::TcpListener::bind(&address)) {
+            Ok(listener) => {
+                info!("Serving metrics on http://{}/metrics.", address);
+                runtime.spawn(metrics.serve(listener));
             }
-        },
-        Err(error) => {
-            warn!("[day] Fetching {} failed: {:?}.", url, error);
+            Err(error) => warn!("Metrics endpoint on {} unavailable: {}.", address, error),
         }
     }
-    COUNT.fetch_add(1, Ordering::Relaxed);
-    {
-        let mut lock = FAILLIST.lock().log_expect("[day] Failed to aquire lock.");
-        lock.push(FailTarget {
-            url: url.to_string(),
-            target_type: TargetType::Day,
-        });
-    }
-    {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.deallocate();
+
+    info!("Waiting for the crawl to finish or Ctrl-C.");
+    let results = runtime.block_on(crawler.run_until(async {
+        let _ = rx.await;
+    }));
+
+    runtime.shutdown_background();
+    if let Some(dashboard) = dashboard {
+        dashboard.join().log_expect("Dashboard thread panicked.");
     }
-} */
-
-// examine the article, test for keyword presence, save target to file
-async fn day_task(url: String) {
-    trace!("Processing day {}.", url);
-    match FETCHCLIENT.fetch(&url).await {
-        Ok(content) => match parse_day_page(content) {
-            Ok(targets) => {
-                for each_target in targets {
-                    COUNT.fetch_add(1, Ordering::Relaxed);
-                    for each in TARGETS {
-                        if each_target
-                            .title
-                            .replace(['\n', '\t', '\r', ' '], "")
-                            .contains(each)
-                        {
-                            info!(
-                                "[article] Found keyword {} in article {}.",
-                                each, &each_target.title
-                            );
-                            SUCCESSES.fetch_add(1, Ordering::Relaxed);
-                            {
-                                let mut lock = SUCCESSLIST
-                                    .lock()
-                                    .log_expect("[article] Failed to aquire lock.");
-                                lock.push(each_target);
-                            }
-                            break;
-                        }
-                    }
-                }
-                {
-                    let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-                    lock.deallocate();
-                }
-                return;
-            }
-            Err(error) => {
-                warn!("[article] Parsing {} failed: {:?}.", url, error);
-            }
-        },
-        Err(error) => {
-            warn!("[article] Fetching {} failed: {:?}.", url, error);
-        }
+
+    info!(
+        "Found {} hit(s) in {} article(s), {} page(s) failed.",
+        results.successes.len(),
+        results.articles,
+        results.failures.len()
+    );
+    if resume {
+        info!(
+            "Skipped {} page(s) completed before.",
+            results.visited.resumed_pages
+        );
     }
-    COUNT.fetch_add(1, Ordering::Relaxed);
-    {
-        let mut lock = FAILLIST
-            .lock()
-            .log_expect("[article] Failed to aquire lock.");
-        lock.push(FailTarget {
-            url: url.to_string(),
-            target_type: TargetType::Article,
-        });
+    info!(
+        "Skipped {} duplicate page(s) and {} duplicate article(s).",
+        results.visited.duplicate_pages, results.visited.duplicate_articles
+    );
+
+    let mut fail_summary: BTreeMap<FailCategory, usize> = BTreeMap::new();
+    for each in results.failures.iter() {
+        *fail_summary.entry(each.category).or_insert(0) += 1;
     }
-    {
-        let mut lock = JOBMANAGER.lock().log_expect("Failed to aquire lock.");
-        lock.deallocate();
+    info!("Failures by category:");
+    for (category, count) in fail_summary {
+        info!("  {:?}: {}", category, count);
     }
 }
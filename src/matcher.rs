@@ -0,0 +1,449 @@
+use crate::normalize::Normalizer;
+use crate::{Alias, Config, Date, Entity, LogExpect, SuccessTarget};
+use aho_corasick::{AhoCorasick, AhoCorasickKind};
+use log::{debug, info};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+// shorter keywords are only matched exactly, one edit would make them match too much
+const MIN_FUZZY_CHARS: usize = 4;
+
+// a normalized alias to look for
+struct Pattern {
+    entity: usize,
+    alias: usize,
+    keyword: String,
+    // edits tolerated when the keyword is not found as is
+    edits: usize,
+    followed_by: Vec<String>,
+    preceded_by: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Pattern {
+    // why the title does not count for the pattern despite containing it at ranges, if it does
+    // not; a fuzzy match has no ranges and only its exclusions apply
+    fn ruled_out(&self, title: &str, ranges: &[Range<usize>]) -> Option<String> {
+        if let Some(exclude) = self
+            .exclude
+            .iter()
+            .find(|exclude| title.contains(exclude.as_str()))
+        {
+            return Some(format!("excluded by {}", exclude));
+        }
+        if ranges.is_empty() {
+            return None;
+        }
+        let in_context = ranges.iter().any(|Range { start, end }| {
+            let (start, end) = (*start, *end);
+            (self.followed_by.is_empty()
+                || self
+                    .followed_by
+                    .iter()
+                    .any(|next| title[end..].starts_with(next.as_str())))
+                && (self.preceded_by.is_empty()
+                    || self
+                        .preceded_by
+                        .iter()
+                        .any(|previous| title[..start].ends_with(previous.as_str())))
+        });
+        if in_context {
+            None
+        } else if self.followed_by.is_empty() {
+            Some(format!("not preceded by {}", self.preceded_by.join("/")))
+        } else if self.preceded_by.is_empty() {
+            Some(format!("not followed by {}", self.followed_by.join("/")))
+        } else {
+            Some(format!(
+                "not between {} and {}",
+                self.preceded_by.join("/"),
+                self.followed_by.join("/")
+            ))
+        }
+    }
+}
+
+// an alias found in a title; confidence is 1.0 for an exact match and lower the more edits it took
+pub struct Match<'a> {
+    pub entity: &'a Entity,
+    pub alias: &'a Alias,
+    pub confidence: f64,
+    // the rule ruling the match out, e.g. "not followed by 大学/医学院"; kept for review
+    pub excluded: Option<String>,
+}
+
+// an alias at a byte range of a normalized title
+pub struct Occurrence<'a> {
+    pub entity: &'a Entity,
+    pub alias: &'a Alias,
+    pub range: Range<usize>,
+    pattern: usize,
+}
+
+// finds the keywords of a crawl in article titles, all of them in one pass over a title
+pub struct Matcher {
+    entities: Vec<Entity>,
+    normalizer: Normalizer,
+    // in matching order
+    patterns: Vec<Pattern>,
+    // finds each distinct keyword; ids index keywords
+    automaton: AhoCorasick,
+    // the patterns of each distinct keyword
+    keywords: Vec<Vec<usize>>,
+    // the patterns tolerating edits, tried one by one: the automaton only finds exact
+    // keywords, so each title not matched exactly costs a distance computation per fuzzy
+    // pattern, slowing matching with thousands of keywords
+    fuzzy: Vec<usize>,
+}
+
+impl Matcher {
+    // keyword groups are tried first, then plain keywords, each standing for itself
+    pub fn new(config: &Config) -> Self {
+        let mut entities = config.entities.clone();
+        entities.extend(
+            config
+                .keywords
+                .iter()
+                .map(|keyword| Entity::keyword(keyword)),
+        );
+        let normalizer = Normalizer::new(&config.ocr_substitutions);
+        let normalize = |texts: &[String]| -> Vec<String> {
+            texts
+                .iter()
+                .map(|text| normalizer.normalize(text))
+                .collect()
+        };
+        let mut patterns = Vec::new();
+        for (entity, each) in entities.iter().enumerate() {
+            for (alias, each) in each.aliases.iter().enumerate() {
+                let keyword = normalizer.normalize(&each.keyword);
+                // a keyword that is all noise would match every title
+                if keyword.is_empty() {
+                    continue;
+                }
+                let edits = match keyword.chars().count() {
+                    _ if each.has_context() => 0,
+                    length if length < MIN_FUZZY_CHARS => 0,
+                    _ => each.edits.unwrap_or(config.max_edits),
+                };
+                patterns.push(Pattern {
+                    entity,
+                    alias,
+                    keyword,
+                    edits,
+                    followed_by: normalize(&each.followed_by),
+                    preceded_by: normalize(&each.preceded_by),
+                    exclude: normalize(&each.exclude),
+                });
+            }
+        }
+        let fuzzy = (0..patterns.len())
+            .filter(|index| patterns[*index].edits > 0)
+            .collect();
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let mut keywords: Vec<Vec<usize>> = Vec::new();
+        for (index, pattern) in patterns.iter().enumerate() {
+            let id = *ids.entry(&pattern.keyword).or_insert_with(|| {
+                keywords.push(Vec::new());
+                keywords.len() - 1
+            });
+            keywords[id].push(index);
+        }
+        let mut distinct = vec![""; keywords.len()];
+        for (keyword, id) in ids {
+            distinct[id] = keyword;
+        }
+        let automaton = AhoCorasick::builder()
+            .kind(Some(AhoCorasickKind::DFA))
+            // CJK keywords share their first bytes, a byte prefilter would hit on every char
+            .prefilter(false)
+            .build(&distinct)
+            .log_expect("Failed to build the keyword matcher.");
+        Matcher {
+            entities,
+            normalizer,
+            patterns,
+            automaton,
+            keywords,
+            fuzzy,
+        }
+    }
+
+    // the title as keywords are looked for in it, see Normalizer
+    pub fn normalize(&self, title: &str) -> String {
+        self.normalizer.normalize(title)
+    }
+
+    // every alias in a normalized title, overlapping ones included, in order of their end
+    pub fn occurrences(&self, title: &str) -> Vec<Occurrence<'_>> {
+        self.automaton
+            .find_overlapping_iter(title)
+            .flat_map(|found| {
+                self.keywords[found.pattern().as_usize()]
+                    .iter()
+                    .map(move |pattern| (*pattern, found.range()))
+            })
+            .map(|(pattern, range)| {
+                let Pattern { entity, alias, .. } = self.patterns[pattern];
+                let entity = &self.entities[entity];
+                Occurrence {
+                    entity,
+                    alias: &entity.aliases[alias],
+                    range,
+                    pattern,
+                }
+            })
+            .collect()
+    }
+
+    fn matched(&self, pattern: &Pattern, confidence: f64, excluded: Option<String>) -> Match<'_> {
+        let entity = &self.entities[pattern.entity];
+        Match {
+            entity,
+            alias: &entity.aliases[pattern.alias],
+            confidence,
+            excluded,
+        }
+    }
+
+    // the first alias found in the normalized title of the article that was in use on its date
+    // and passes its rules; failing that, the closest one within its edit tolerance; failing
+    // that, the first one ruled out
+    pub fn find(&self, target: &SuccessTarget, date: Option<Date>) -> Option<Match<'_>> {
+        let title = self.normalize(&target.title);
+        let in_use = |pattern: &&Pattern| {
+            let valid = self.entities[pattern.entity].aliases[pattern.alias].valid_on(date);
+            if !valid {
+                debug!(
+                    "Keyword {} in article {} is outside its dates.",
+                    pattern.keyword, &target.title
+                );
+            }
+            valid
+        };
+        let mut ruled_out = None;
+        let mut best: Option<(&Pattern, f64)> = None;
+        // the ranges of each pattern found, in matching order
+        let mut found: BTreeMap<usize, Vec<Range<usize>>> = BTreeMap::new();
+        for occurrence in self.occurrences(&title) {
+            found
+                .entry(occurrence.pattern)
+                .or_default()
+                .push(occurrence.range);
+        }
+        for (pattern, ranges) in &found {
+            let pattern = &self.patterns[*pattern];
+            if !in_use(&pattern) {
+                continue;
+            }
+            match pattern.ruled_out(&title, ranges) {
+                None => {
+                    best = Some((pattern, 1.0));
+                    break;
+                }
+                Some(rule) => {
+                    ruled_out.get_or_insert((pattern, 1.0, rule));
+                }
+            }
+        }
+        if best.is_none() && !self.fuzzy.is_empty() {
+            let chars: Vec<char> = title.chars().collect();
+            let fuzzy = self
+                .fuzzy
+                .iter()
+                .filter(|index| !found.contains_key(index))
+                .map(|index| &self.patterns[*index]);
+            for pattern in fuzzy.filter(in_use) {
+                let keyword: Vec<char> = pattern.keyword.chars().collect();
+                let edits = distance(&keyword, &chars);
+                if edits > pattern.edits {
+                    continue;
+                }
+                let confidence = 1.0 - edits as f64 / keyword.len() as f64;
+                if let Some(rule) = pattern.ruled_out(&title, &[]) {
+                    ruled_out.get_or_insert((pattern, confidence, rule));
+                } else if best.is_none_or(|(_, best)| confidence > best) {
+                    best = Some((pattern, confidence));
+                }
+            }
+        }
+        let matched = match (best, ruled_out) {
+            (Some((pattern, confidence)), _) => Some(self.matched(pattern, confidence, None)),
+            (None, Some((pattern, confidence, rule))) => {
+                Some(self.matched(pattern, confidence, Some(rule)))
+            }
+            (None, None) => None,
+        };
+        match &matched {
+            Some(Match {
+                excluded: Some(rule),
+                alias,
+                ..
+            }) => info!(
+                "Ruled out keyword {} in article {}: {}.",
+                alias.keyword, &target.title, rule
+            ),
+            Some(found) => info!(
+                "Found keyword {} ({}, confidence {:.2}) in article {} ({}).",
+                found.alias.keyword,
+                found.entity.name,
+                found.confidence,
+                &target.title,
+                target.page.as_deref().unwrap_or("unknown page")
+            ),
+            None => debug!("No keyword in article {}.", &target.title),
+        }
+        matched
+    }
+}
+
+// fewest insertions, deletions and substitutions turning keyword into any part of text
+fn distance(keyword: &[char], text: &[char]) -> usize {
+    // edits for the keyword prefix of each length against text ending at the current char
+    let mut column: Vec<usize> = (0..=keyword.len()).collect();
+    let mut best = keyword.len();
+    for c in text {
+        let mut diagonal = column[0];
+        // a match may start anywhere in text
+        column[0] = 0;
+        for (index, k) in keyword.iter().enumerate() {
+            let above = column[index + 1];
+            column[index + 1] = (diagonal + usize::from(k != c))
+                .min(above + 1)
+                .min(column[index] + 1);
+            diagonal = above;
+        }
+        best = best.min(column[keyword.len()]);
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(title: &str) -> SuccessTarget {
+        SuccessTarget {
+            title: title.to_string(),
+            url: String::new(),
+            page: None,
+            page_number: None,
+            keyword: None,
+            entity: None,
+            confidence: None,
+            category: None,
+            row: None,
+        }
+    }
+
+    fn matcher(max_edits: usize) -> Matcher {
+        Matcher::new(&Config {
+            keywords: vec!["华中工学院".to_string()],
+            entities: vec![Entity::new("Tongji Medical", ["同济医学院"])],
+            ocr_substitutions: vec![("＠".to_string(), String::new())],
+            max_edits,
+            ..Config::default()
+        })
+    }
+
+    fn found(matcher: &Matcher, title: &str) -> Option<(String, String, f64)> {
+        matcher.find(&article(title), None).map(|found| {
+            (
+                found.entity.name.clone(),
+                found.alias.keyword.clone(),
+                found.confidence,
+            )
+        })
+    }
+
+    #[test]
+    fn matches_normalized_titles() {
+        let matcher = matcher(0);
+        assert_eq!(
+            found(&matcher, "同濟＠醫學院　新生入學"),
+            Some(("Tongji Medical".to_string(), "同济医学院".to_string(), 1.0))
+        );
+        assert_eq!(
+            found(&matcher, "華中 工學院"),
+            Some(("华中工学院".to_string(), "华中工学院".to_string(), 1.0))
+        );
+        assert_eq!(found(&matcher, "华中师范学院"), None);
+    }
+
+    #[test]
+    fn matches_damaged_keywords_with_less_confidence() {
+        let chars = |text: &str| text.chars().collect::<Vec<char>>();
+        assert_eq!(distance(&chars("华中工学院"), &chars("学习华中工学院")), 0);
+        assert_eq!(distance(&chars("华中工学院"), &chars("华中丁学院开学")), 1);
+        assert_eq!(distance(&chars("华中工学院"), &chars("华中学院")), 1);
+
+        assert_eq!(found(&matcher(0), "华中工孛院开学"), None);
+        let (_, keyword, confidence) = found(&matcher(1), "华中工孛院开学").unwrap();
+        assert_eq!(keyword, "华中工学院");
+        assert!((confidence - 0.8).abs() < 1e-9);
+        assert_eq!(found(&matcher(1), "华中师范学院"), None);
+    }
+
+    #[test]
+    fn rules_rule_out_keywords_out_of_context() {
+        let tongji = Alias {
+            followed_by: vec!["大学".to_string(), "医学院".to_string()],
+            exclude: vec!["同舟共济".to_string()],
+            ..Alias::new("同济")
+        };
+        let matcher = Matcher::new(&Config {
+            entities: vec![Entity {
+                name: "Tongji".to_string(),
+                aliases: vec![tongji],
+            }],
+            ..Config::default()
+        });
+        let rule = |title: &str| {
+            matcher
+                .find(&article(title), None)
+                .map(|found| found.excluded)
+        };
+        assert_eq!(rule("同济大学举行运动会"), Some(None));
+        // one occurrence in context is enough
+        assert_eq!(rule("安危同济 同济医学院师生"), Some(None));
+        assert_eq!(
+            rule("拉丁美洲各国人民必须生死与共安危同济"),
+            Some(Some("not followed by 大学/医学院".to_string()))
+        );
+        assert_eq!(
+            rule("同舟共济 同济大学"),
+            Some(Some("excluded by 同舟共济".to_string()))
+        );
+        assert_eq!(rule("春耕生产"), None);
+    }
+
+    #[test]
+    fn finds_every_occurrence_in_one_pass() {
+        let matcher = Matcher::new(&Config {
+            keywords: vec!["工学院".to_string(), "华中工学院".to_string()],
+            entities: vec![Entity::new("HUST", ["华中工学院"])],
+            ..Config::default()
+        });
+        let title = matcher.normalize("華中工學院与西北工学院");
+        let found: Vec<(&str, &str, Range<usize>)> = matcher
+            .occurrences(&title)
+            .into_iter()
+            .map(|each| {
+                (
+                    each.entity.name.as_str(),
+                    each.alias.keyword.as_str(),
+                    each.range,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("HUST", "华中工学院", 0..15),
+                ("华中工学院", "华中工学院", 0..15),
+                ("工学院", "工学院", 6..15),
+                ("工学院", "工学院", 24..33),
+            ]
+        );
+    }
+}
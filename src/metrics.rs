@@ -0,0 +1,324 @@
+use crate::{Event, FailCategory, LogExpect, Sink, TargetType};
+use log::{debug, warn};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// the largest metrics request read, and how long a client may take to send it
+const REQUEST_LIMIT: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// upper bounds of the request latency buckets, in seconds
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Default)]
+struct Counters {
+    // every request sent, by its HTTP status, or how it failed without one, e.g. timeout
+    requests: BTreeMap<String, u64>,
+    retries: u64,
+    bytes: u64,
+    // counts per bucket of LATENCY_BUCKETS, then the sum of all latencies
+    latency: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+    latency_count: u64,
+    // pages handled, by level and whether they were parsed or failed
+    pages: BTreeMap<(&'static str, &'static str), u64>,
+    // pages linked from handled listings, by level
+    discovered: BTreeMap<&'static str, u64>,
+    articles: u64,
+    hits: BTreeMap<String, u64>,
+    excluded: BTreeMap<String, u64>,
+    running: usize,
+}
+
+// well-defined crawl metrics in the Prometheus text format, fed as a sink
+#[derive(Clone, Default)]
+pub struct Metrics {
+    counters: Arc<Mutex<Counters>>,
+}
+
+fn level_label(level: TargetType) -> &'static str {
+    match level {
+        TargetType::Index => "index",
+        TargetType::Year => "year",
+        TargetType::Month => "month",
+        TargetType::Day => "day",
+        TargetType::Page => "page",
+        TargetType::Article => "article",
+    }
+}
+
+fn child_label(level: TargetType) -> &'static str {
+    match level {
+        TargetType::Index => "year",
+        TargetType::Year => "month",
+        TargetType::Month => "day",
+        _ => "page",
+    }
+}
+
+fn category_label(category: FailCategory) -> &'static str {
+    match category {
+        FailCategory::Timeout => "timeout",
+        FailCategory::Connection => "connection",
+        FailCategory::HttpStatus => "http_status",
+        FailCategory::Decode => "decode",
+        FailCategory::Parsing => "parsing",
+        FailCategory::General => "general",
+    }
+}
+
+fn status_label(status: Option<u16>, category: FailCategory) -> String {
+    status.map_or(category_label(category).to_string(), |status| {
+        status.to_string()
+    })
+}
+
+fn by_keyword(counts: &BTreeMap<String, u64>) -> Vec<(String, String)> {
+    counts
+        .iter()
+        .map(|(keyword, count)| {
+            let keyword = keyword.replace('\\', "\\\\").replace('"', "\\\"");
+            (format!("{{keyword=\"{}\"}}", keyword), count.to_string())
+        })
+        .collect()
+}
+
+impl Counters {
+    fn observe(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.latency.iter_mut()) {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        self.latency_sum += seconds;
+        self.latency_count += 1;
+    }
+
+    fn update(&mut self, event: &Event) {
+        match event {
+            Event::PageFetched {
+                status,
+                bytes,
+                elapsed,
+                ..
+            } => {
+                *self.requests.entry(status.to_string()).or_insert(0) += 1;
+                self.bytes += *bytes as u64;
+                self.observe(*elapsed);
+            }
+            Event::RequestRetried {
+                category, status, ..
+            } => {
+                *self
+                    .requests
+                    .entry(status_label(*status, *category))
+                    .or_insert(0) += 1;
+                self.retries += 1;
+            }
+            Event::PageFailed(failure) => {
+                // parsing failures come after a successful request
+                if failure.category != FailCategory::Parsing {
+                    *self
+                        .requests
+                        .entry(status_label(failure.status, failure.category))
+                        .or_insert(0) += 1;
+                }
+                *self
+                    .pages
+                    .entry((level_label(failure.target_type), "failed"))
+                    .or_insert(0) += 1;
+            }
+            Event::LevelExpanded {
+                level,
+                pages,
+                articles,
+                ..
+            } => {
+                *self
+                    .pages
+                    .entry((level_label(*level), "parsed"))
+                    .or_insert(0) += 1;
+                if *pages > 0 {
+                    *self.discovered.entry(child_label(*level)).or_insert(0) += *pages as u64;
+                }
+                self.articles += *articles as u64;
+            }
+            Event::HitFound(hit) => {
+                let keyword = hit.keyword.clone().unwrap_or_default();
+                *self.hits.entry(keyword).or_insert(0) += 1;
+            }
+            Event::HitExcluded(excluded) => {
+                let keyword = excluded.target.keyword.clone().unwrap_or_default();
+                *self.excluded.entry(keyword).or_insert(0) += 1;
+            }
+            Event::Progress { running, .. } => self.running = *running,
+            Event::Finished { .. } => {}
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+            let _ = writeln!(out, "# HELP webb_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE webb_{} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "webb_{}{} {}", name, labels, value);
+            }
+        };
+        let single = |value: &dyn std::fmt::Display| vec![(String::new(), value.to_string())];
+
+        family(
+            "requests_total",
+            "counter",
+            "HTTP requests sent, by status.",
+            self.requests
+                .iter()
+                .map(|(status, count)| (format!("{{status=\"{}\"}}", status), count.to_string()))
+                .collect(),
+        );
+        family(
+            "retries_total",
+            "counter",
+            "Failed requests that were sent again.",
+            single(&self.retries),
+        );
+        family(
+            "response_bytes_total",
+            "counter",
+            "Bytes of pages fetched.",
+            single(&self.bytes),
+        );
+        let mut latency: Vec<(String, String)> = LATENCY_BUCKETS
+            .iter()
+            .zip(self.latency.iter())
+            .map(|(bound, count)| (format!("_bucket{{le=\"{}\"}}", bound), count.to_string()))
+            .collect();
+        latency.push((
+            "_bucket{le=\"+Inf\"}".to_string(),
+            self.latency_count.to_string(),
+        ));
+        latency.push(("_sum".to_string(), self.latency_sum.to_string()));
+        latency.push(("_count".to_string(), self.latency_count.to_string()));
+        family(
+            "request_duration_seconds",
+            "histogram",
+            "Duration of successful requests.",
+            latency,
+        );
+        family(
+            "pages_total",
+            "counter",
+            "Pages handled, by level and result.",
+            self.pages
+                .iter()
+                .map(|((level, result), count)| {
+                    (
+                        format!("{{level=\"{}\",result=\"{}\"}}", level, result),
+                        count.to_string(),
+                    )
+                })
+                .collect(),
+        );
+        family(
+            "pages_discovered_total",
+            "counter",
+            "Pages linked from handled listings, by level.",
+            self.discovered
+                .iter()
+                .map(|(level, count)| (format!("{{level=\"{}\"}}", level), count.to_string()))
+                .collect(),
+        );
+        family(
+            "articles_total",
+            "counter",
+            "Articles listed on handled pages.",
+            single(&self.articles),
+        );
+        family(
+            "hits_total",
+            "counter",
+            "Articles matching a keyword, by keyword.",
+            by_keyword(&self.hits),
+        );
+        family(
+            "hits_excluded_total",
+            "counter",
+            "Articles with a keyword ruled out by one of its rules, by keyword.",
+            by_keyword(&self.excluded),
+        );
+        family(
+            "jobs_running",
+            "gauge",
+            "Pages scheduled and not yet handled.",
+            single(&self.running),
+        );
+        out
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    pub fn render(&self) -> String {
+        self.counters
+            .lock()
+            .log_expect("Failed to aquire lock.")
+            .render()
+    }
+
+    // answer every HTTP request on the listener with the current metrics
+    pub async fn serve(self, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(self.clone().respond(stream));
+                }
+                Err(error) => warn!("Accepting a metrics connection failed: {}.", error),
+            }
+        }
+    }
+
+    async fn respond(self, mut stream: TcpStream) {
+        // a client sending too much or too slowly is dropped rather than held open
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        let read = async {
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buffer).await {
+                    Ok(0) | Err(_) => return false,
+                    Ok(read) if request.len() + read > REQUEST_LIMIT => return false,
+                    Ok(read) => request.extend_from_slice(&buffer[..read]),
+                }
+            }
+            true
+        };
+        if !matches!(tokio::time::timeout(REQUEST_TIMEOUT, read).await, Ok(true)) {
+            warn!("Dropping a metrics request that was too large or too slow.");
+            return;
+        }
+        debug!("Serving metrics.");
+        let body = self.render();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+}
+
+impl Sink for Metrics {
+    fn event(&self, event: &Event) {
+        self.counters
+            .lock()
+            .log_expect("Failed to aquire lock.")
+            .update(event);
+    }
+}
@@ -0,0 +1,128 @@
+// the block of common CJK characters, where the traditional ones of the table are
+const CJK: std::ops::RangeInclusive<char> = '\u{4e00}'..='\u{9fff}';
+
+lazy_static::lazy_static! {
+    // the simplified form of each character of the CJK block, looked up for every title
+    static ref SIMPLIFIED: Vec<char> = {
+        let mut table: Vec<char> = CJK.collect();
+        for pair in include_str!("traditional.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(str::split_whitespace)
+        {
+            let mut chars = pair.chars();
+            if let (Some(traditional), Some(simplified)) = (chars.next(), chars.next()) {
+                if CJK.contains(&traditional) {
+                    table[traditional as usize - *CJK.start() as usize] = simplified;
+                }
+            }
+        }
+        table
+    };
+}
+
+// full-width forms of ASCII, e.g. ＡＢＣ１２３，
+const FULL_WIDTH: std::ops::RangeInclusive<char> = '\u{ff01}'..='\u{ff5e}';
+const FULL_WIDTH_OFFSET: u32 = 0xfee0;
+
+// one spelling of the same punctuation, applied after full-width folding
+fn fold_punctuation(c: char) -> char {
+    match c {
+        '。' | '｡' => '.',
+        '、' | '､' => ',',
+        '“' | '”' | '「' | '」' | '『' | '』' | '〝' | '〞' => '"',
+        '‘' | '’' => '\'',
+        '《' | '〈' => '<',
+        '》' | '〉' => '>',
+        '【' | '〔' | '〖' => '[',
+        '】' | '〕' | '〗' => ']',
+        '—' | '–' | '―' | '‐' | '－' => '-',
+        '…' => '.',
+        '・' | '•' | '‧' | '∙' => '·',
+        c => c,
+    }
+}
+
+// brings titles and keywords to one spelling before they are compared:
+// full-width to half-width, no whitespace, simplified characters, folded punctuation,
+// then OCR noise replaced, e.g. the placeholder ＠ dropped
+pub struct Normalizer {
+    // normalized noise and its replacement, longest first
+    substitutions: Vec<(String, String)>,
+}
+
+impl Normalizer {
+    pub fn new(substitutions: &[(String, String)]) -> Self {
+        let mut substitutions: Vec<(String, String)> = substitutions
+            .iter()
+            .map(|(noise, replacement)| (fold(noise), fold(replacement)))
+            .filter(|(noise, _)| !noise.is_empty())
+            .collect();
+        substitutions.sort_by_key(|(noise, _)| std::cmp::Reverse(noise.chars().count()));
+        Normalizer { substitutions }
+    }
+
+    pub fn normalize(&self, text: &str) -> String {
+        let mut text = fold(text);
+        for (noise, replacement) in &self.substitutions {
+            if text.contains(noise.as_str()) {
+                text = text.replace(noise.as_str(), replacement);
+            }
+        }
+        text
+    }
+}
+
+fn fold(text: &str) -> String {
+    let simplified: &[char] = &SIMPLIFIED;
+    text.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '\u{200b}' | '\u{feff}'))
+        .map(|c| {
+            if c.is_ascii() {
+                return c;
+            }
+            if CJK.contains(&c) {
+                return simplified[c as usize - *CJK.start() as usize];
+            }
+            if FULL_WIDTH.contains(&c) {
+                return char::from_u32(c as u32 - FULL_WIDTH_OFFSET).unwrap_or(c);
+            }
+            fold_punctuation(c)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_lists_pairs() {
+        for line in include_str!("traditional.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+        {
+            for pair in line.split_whitespace() {
+                assert_eq!(pair.chars().count(), 2, "{}", pair);
+                assert!(CJK.contains(&pair.chars().next().unwrap()), "{}", pair);
+            }
+        }
+        assert_eq!(fold("華"), "华");
+        assert_eq!(fold("鬱"), "郁");
+    }
+
+    #[test]
+    fn normalizes_titles() {
+        let normalizer = Normalizer::new(&[("＠".to_string(), String::new())]);
+        assert_eq!(normalizer.normalize("审＠同济　同学"), "审同济同学");
+        assert_eq!(
+            normalizer.normalize("華中工學院\u{a0}１９５８年"),
+            "华中工学院1958年"
+        );
+        assert_eq!(
+            normalizer.normalize("《同濟醫學院》，“新生”"),
+            "<同济医学院>,\"新生\""
+        );
+        assert_eq!(normalizer.normalize("同济 医科\n大学"), "同济医科大学");
+    }
+}
@@ -1,32 +1,63 @@
-use crate::{Error, SuccessTarget, ROOT};
-use scraper::{Html, Selector};
-// parse article urls
-pub fn parse_day_page(content: String) -> Result<Vec<SuccessTarget>, Error> {
-    let mut results = Vec::new();
+use crate::{
+    links::canonicalize, links::resolve, links::without_fragment, links::Url, parsers::link_href,
+    parsers::ListSelectors, parsers::Parsed, Error,
+};
+use scraper::Html;
+// parse page urls, every article link points into the page it is printed on
+pub fn parse_day_page(
+    content: String,
+    url: &Url,
+    selectors: &ListSelectors,
+) -> Result<Parsed<Url>, Error> {
+    let mut results = Parsed::new(url);
 
     let structure = Html::parse_document(&content);
 
-    let li_selector = Selector::parse(".card-text")
-        .map_err(|_| Error::ParsingError("li selector".to_string()))?;
+    for each in structure.select(&selectors.item) {
+        let page = link_href(each, &selectors.link)
+            .and_then(|href| resolve(url, href))
+            .map(|article| without_fragment(&article));
+        // the first spelling linked of a page is kept, e.g. .../05/04/ over .../5/4/
+        let key = |page: &Url| canonicalize(page.clone(), url.scheme());
+        if matches!(&page, Ok(page) if results.items.iter().any(|seen| key(seen) == key(page))) {
+            continue;
+        }
+        results.push(each, page)?;
+    }
+
+    results.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::SITE;
+    use crate::parsers::Selectors;
 
-    for each in structure.select(&li_selector) {
-        let a_selector =
-            Selector::parse("a").map_err(|_| Error::ParsingError("link selector".to_string()))?;
-        let link = each
-            .select(&a_selector)
-            .next()
-            .ok_or(Error::ParsingError("link".to_string()))?;
-        let url = format!(
-            "{}{}",
-            ROOT,
-            link.value()
-                .attr("href")
-                .ok_or(Error::ParsingError("link href".to_string()))?
-                .to_string(),
+    fn url() -> Url {
+        SITE.join("1959/12/5/").unwrap()
+    }
+
+    #[test]
+    fn parses_distinct_page_urls() {
+        let content = include_str!("../../fixtures/day.html").to_string();
+        let parsed = parse_day_page(content, &url(), &Selectors::default().day).unwrap();
+        let urls: Vec<&str> = parsed.items.iter().map(Url::as_str).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://cn.govopendata.com/renminribao/1959/12/5/1/",
+                "https://cn.govopendata.com/renminribao/1959/12/05/04/",
+                "https://cn.govopendata.com/renminribao/1959/12/5/6/",
+            ]
         );
-        let title = link.text().collect::<String>();
-        results.push(SuccessTarget { title, url });
     }
 
-    Ok(results)
+    #[test]
+    fn empty_day_is_not_a_failure() {
+        let content = include_str!("../../fixtures/day_empty.html").to_string();
+        let parsed = parse_day_page(content, &url(), &Selectors::default().day).unwrap();
+        assert!(parsed.items.is_empty());
+        assert!(parsed.warnings.is_empty());
+    }
 }
@@ -1,11 +1,88 @@
-mod article;
 mod day;
 mod index;
 mod month;
+mod page;
+mod selectors;
 mod year;
 
-pub use article::parse_article;
 pub use day::parse_day_page;
 pub use index::parse_index_page;
 pub use month::parse_month_page;
+pub use page::{page_number, parse_page_page};
+pub use selectors::{ListSelectors, PageSelectors, Selectors, SiteConfig};
 pub use year::parse_year_page;
+
+use crate::{links::Url, Error};
+use log::warn;
+use scraper::{ElementRef, Selector};
+
+// a list item that could not be parsed, kept with its markup for review
+#[derive(serde::Serialize, Debug)]
+pub struct ParseWarning {
+    pub url: String,
+    pub selector: String,
+    pub fragment: String,
+}
+
+// items extracted from a listing page along with the ones that had to be skipped
+pub struct Parsed<T> {
+    url: String,
+    pub items: Vec<T>,
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<T> Parsed<T> {
+    pub fn new(url: &Url) -> Self {
+        Parsed {
+            url: url.to_string(),
+            items: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    // malformed items are skipped, in strict mode the crawler fails the page over them
+    pub fn push(&mut self, element: ElementRef, item: Result<T, Error>) -> Result<(), Error> {
+        match item {
+            Ok(inner) => self.items.push(inner),
+            Err(Error::ParsingError(selector)) => {
+                warn!("Skipping malformed {} on {}.", selector, self.url);
+                self.warnings.push(ParseWarning {
+                    url: self.url.clone(),
+                    selector,
+                    fragment: element.html(),
+                });
+            }
+            Err(error) => return Err(error),
+        }
+        Ok(())
+    }
+
+    // a page is only failed when none of its items could be parsed
+    pub fn finish(self) -> Result<Parsed<T>, Error> {
+        if self.items.is_empty() {
+            if let Some(first) = self.warnings.first() {
+                return Err(Error::ParsingError(first.selector.clone()));
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Parsed<U> {
+        Parsed {
+            url: self.url,
+            items: self.items.into_iter().map(f).collect(),
+            warnings: self.warnings,
+        }
+    }
+}
+
+// href of the first link within a list item
+fn link_href<'a>(element: ElementRef<'a>, link: &Selector) -> Result<&'a str, Error> {
+    element
+        .select(link)
+        .next()
+        .ok_or(Error::ParsingError("link".to_string()))?
+        .value()
+        .attr("href")
+        .ok_or(Error::ParsingError("link href".to_string()))
+}
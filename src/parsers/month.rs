@@ -1,27 +1,78 @@
-use crate::{Error, ROOT};
-use scraper::{Html, Selector};
+use crate::{
+    links::resolve, links::Url, parsers::link_href, parsers::ListSelectors, parsers::Parsed, Error,
+};
+use scraper::Html;
 // parse day urls
-pub fn parse_month_page(content: String) -> Result<Vec<String>, Error> {
-    let mut results = Vec::new();
+pub fn parse_month_page(
+    content: String,
+    url: &Url,
+    selectors: &ListSelectors,
+) -> Result<Parsed<Url>, Error> {
+    let mut results = Parsed::new(url);
+
     let structure = Html::parse_document(&content);
 
-    let li_selector = Selector::parse("li.list-group-item")
-        .map_err(|_| Error::ParsingError("li selector".to_string()))?;
-    for each in structure.select(&li_selector) {
-        let a_selector =
-            Selector::parse("a").map_err(|_| Error::ParsingError("link selector".to_string()))?;
-        let link = each
-            .select(&a_selector)
-            .next()
-            .ok_or(Error::ParsingError("link".to_string()))?;
-        results.push(format!(
-            "{}{}",
-            ROOT,
-            link.value()
-                .attr("href")
-                .ok_or(Error::ParsingError("link href".to_string()))?
-                .to_string(),
+    for each in structure.select(&selectors.item) {
+        let link = link_href(each, &selectors.link).and_then(|href| resolve(url, href));
+        results.push(each, link)?;
+    }
+
+    results.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::SITE;
+    use crate::parsers::Selectors;
+
+    fn url() -> Url {
+        SITE.join("1974/11/").unwrap()
+    }
+
+    #[test]
+    fn parses_day_urls() {
+        let content = include_str!("../../fixtures/month.html").to_string();
+        let parsed = parse_month_page(content, &url(), &Selectors::default().month).unwrap();
+        let urls: Vec<&str> = parsed.items.iter().map(Url::as_str).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://cn.govopendata.com/renminribao/1974/11/01/",
+                "https://cn.govopendata.com/renminribao/1974/11/06/",
+                "https://cn.govopendata.com/renminribao/1974/11/30/",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_items_without_href() {
+        let content = include_str!("../../fixtures/month_missing_href.html").to_string();
+        let parsed = parse_month_page(content, &url(), &Selectors::default().month).unwrap();
+        let urls: Vec<&str> = parsed.items.iter().map(Url::as_str).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://cn.govopendata.com/renminribao/1974/11/01/",
+                "https://cn.govopendata.com/renminribao/1974/11/04/",
+            ]
+        );
+        let selectors: Vec<&str> = parsed
+            .warnings
+            .iter()
+            .map(|warning| warning.selector.as_str())
+            .collect();
+        assert_eq!(selectors, ["link href", "link"]);
+        assert!(parsed.warnings[0].fragment.contains("11月2日"));
+        assert!(parsed.warnings[1].fragment.contains("11月3日"));
+    }
+
+    #[test]
+    fn fails_when_no_item_parses() {
+        let content = include_str!("../../fixtures/month_malformed.html").to_string();
+        assert!(matches!(
+            parse_month_page(content, &url(), &Selectors::default().month),
+            Err(Error::ParsingError(selector)) if selector == "link href"
         ));
     }
-    Ok(results)
 }
@@ -0,0 +1,128 @@
+use crate::{
+    links::without_fragment, links::Url, parsers::PageSelectors, parsers::Parsed, Error,
+    SuccessTarget,
+};
+use scraper::Html;
+
+// page urls end with year, month, day and page number, e.g. /renminribao/1959/12/5/4/;
+// shorter urls, such as that of the day /renminribao/1959/12/5/, have no page number
+pub fn page_number(url: &Url) -> Option<u32> {
+    let numbers: Vec<u32> = url
+        .path_segments()?
+        .filter(|segment| !segment.is_empty())
+        .rev()
+        .map_while(|segment| segment.parse().ok())
+        .collect();
+    match numbers[..] {
+        [page, _, _, _] => Some(page),
+        _ => None,
+    }
+}
+
+// parse article titles of a page (版面), tagging each with the page name
+pub fn parse_page_page(
+    content: String,
+    url: &Url,
+    selectors: &PageSelectors,
+) -> Result<Parsed<SuccessTarget>, Error> {
+    let mut articles = Parsed::new(url);
+    let structure = Html::parse_document(&content);
+    let number = page_number(url);
+
+    let name = structure
+        .select(&selectors.heading)
+        .next()
+        .map(|heading| heading.text().collect::<String>().trim().to_string())
+        .filter(|heading| !heading.is_empty())
+        .or_else(|| number.map(|number| format!("第{}版", number)))
+        .ok_or(Error::ParsingError("page heading".to_string()))?;
+
+    let base = without_fragment(url);
+
+    for each in structure.select(&selectors.item) {
+        let article = each
+            .select(&selectors.link)
+            .next()
+            .ok_or(Error::ParsingError("link".to_string()))
+            .and_then(|link| {
+                let id = link
+                    .value()
+                    .id()
+                    .ok_or(Error::ParsingError("article id".to_string()))?;
+                let mut article = base.clone();
+                article.set_fragment(Some(id));
+                Ok(SuccessTarget {
+                    title: link.text().collect::<String>(),
+                    url: article.to_string(),
+                    page: Some(name.clone()),
+                    page_number: number,
+                    keyword: None,
+                    entity: None,
+                    confidence: None,
+                    category: None,
+                    row: None,
+                })
+            });
+        articles.push(each, article)?;
+    }
+
+    articles.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::SITE;
+    use crate::parsers::Selectors;
+
+    #[test]
+    fn parses_articles_with_page_name() {
+        let content = include_str!("../../fixtures/page.html").to_string();
+        let url = SITE.join("1959/12/5/4/").unwrap();
+        let parsed = parse_page_page(content, &url, &Selectors::default().page).unwrap();
+        let articles: Vec<(&str, &str)> = parsed
+            .items
+            .iter()
+            .map(|article| (article.title.as_str(), article.url.as_str()))
+            .collect();
+        assert_eq!(
+            articles,
+            [
+                (
+                    "既解决了生产问题　又提高了工作能力  华中工学院下厂采集研究课题",
+                    "https://cn.govopendata.com/renminribao/1959/12/5/4/#234881"
+                ),
+                (
+                    "北京大学开展科学研究",
+                    "https://cn.govopendata.com/renminribao/1959/12/5/4/#234882"
+                ),
+            ]
+        );
+        for article in &parsed.items {
+            assert_eq!(article.page.as_deref(), Some("第4版 要闻"));
+            assert_eq!(article.page_number, Some(4));
+        }
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].selector, "article id");
+    }
+
+    #[test]
+    fn falls_back_to_page_number_without_heading() {
+        let content = include_str!("../../fixtures/page_untitled.html").to_string();
+        let url = SITE.join("1956/8/26/7/").unwrap();
+        let parsed = parse_page_page(content, &url, &Selectors::default().page).unwrap();
+        assert_eq!(parsed.items.len(), 1);
+        assert_eq!(parsed.items[0].page.as_deref(), Some("第7版"));
+    }
+
+    #[test]
+    fn reads_page_number_from_url() {
+        let article = SITE.join("1959/12/5/4/#234881").unwrap();
+        assert_eq!(page_number(&article), Some(4));
+        assert_eq!(page_number(&SITE.join("1956/8/26/7/").unwrap()), Some(7));
+        assert_eq!(page_number(&SITE.join("1962/3/24/12").unwrap()), Some(12));
+        assert_eq!(page_number(&SITE.join("1959/12/5/").unwrap()), None);
+        assert_eq!(page_number(&SITE.join("1959/12/").unwrap()), None);
+        assert_eq!(page_number(&SITE), None);
+    }
+}
@@ -0,0 +1,125 @@
+// built-in selectors, used for every one the site file leaves out
+const LIST_ITEM: &str = "li.list-group-item";
+const DAY_ITEM: &str = ".card-text";
+const LINK: &str = "a";
+const PAGE_HEADING: &str = "h1";
+const CARD: &str = ".card";
+
+use crate::{FileSource, LogExpect};
+use scraper::Selector;
+use std::path::Path;
+
+// css selectors describing the markup of the archive, e.g. from site.yaml;
+// any selector left out keeps its built-in default
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    pub index: ListConfig,
+    pub year: ListConfig,
+    pub month: ListConfig,
+    pub day: ListConfig,
+    pub page: PageConfig,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListConfig {
+    pub item: Option<String>,
+    pub link: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PageConfig {
+    pub heading: Option<String>,
+    pub item: Option<String>,
+    pub link: Option<String>,
+}
+
+// compiled once when the crawler is built, shared by every parser
+pub struct Selectors {
+    pub index: ListSelectors,
+    pub year: ListSelectors,
+    pub month: ListSelectors,
+    pub day: ListSelectors,
+    pub page: PageSelectors,
+}
+
+pub struct ListSelectors {
+    pub item: Selector,
+    pub link: Selector,
+}
+
+pub struct PageSelectors {
+    pub heading: Selector,
+    pub item: Selector,
+    pub link: Selector,
+}
+
+fn compile(key: &str, selector: &Option<String>, default: &str) -> Result<Selector, String> {
+    let selector = selector.as_deref().unwrap_or(default);
+    Selector::parse(selector).map_err(|_| format!("{}: invalid selector \"{}\"", key, selector))
+}
+
+impl ListConfig {
+    fn compile(&self, level: &str, item: &str) -> Result<ListSelectors, String> {
+        Ok(ListSelectors {
+            item: compile(&format!("{}.item", level), &self.item, item)?,
+            link: compile(&format!("{}.link", level), &self.link, LINK)?,
+        })
+    }
+}
+
+impl SiteConfig {
+    // the first invalid selector is named by its key, e.g. day.item
+    pub fn compile(&self) -> Result<Selectors, String> {
+        Ok(Selectors {
+            index: self.index.compile("index", LIST_ITEM)?,
+            year: self.year.compile("year", LIST_ITEM)?,
+            month: self.month.compile("month", LIST_ITEM)?,
+            day: self.day.compile("day", DAY_ITEM)?,
+            page: PageSelectors {
+                heading: compile("page.heading", &self.page.heading, PAGE_HEADING)?,
+                item: compile("page.item", &self.page.item, CARD)?,
+                link: compile("page.link", &self.page.link, LINK)?,
+            },
+        })
+    }
+}
+
+impl Selectors {
+    // the selectors of a site file, YAML or TOML, checked before anything is fetched
+    pub fn load(path: &Path) -> Result<Selectors, String> {
+        FileSource::new(path)
+            .try_load::<SiteConfig>()?
+            .compile()
+            .map_err(|error| format!("{}: {}", path.to_string_lossy(), error))
+    }
+}
+
+impl Default for Selectors {
+    fn default() -> Self {
+        SiteConfig::default()
+            .compile()
+            .log_expect("Invalid built-in selectors.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_left_out_selectors() {
+        let config: SiteConfig = serde_yaml::from_str("day:\n  link: a.title\n").unwrap();
+        let selectors = config.compile().unwrap();
+        assert_eq!(selectors.day.item, Selector::parse(DAY_ITEM).unwrap());
+        assert_eq!(selectors.day.link, Selector::parse("a.title").unwrap());
+        let config: SiteConfig = serde_yaml::from_str("page:\n  item: \"[\"\n").unwrap();
+        assert_eq!(
+            config.compile().err(),
+            Some("page.item: invalid selector \"[\"".to_string())
+        );
+        assert!(serde_yaml::from_str::<SiteConfig>("day:\n  items: li\n").is_err());
+    }
+}
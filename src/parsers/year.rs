@@ -1,30 +1,45 @@
-use crate::{Error, ROOT};
-use scraper::{Html, Selector};
+use crate::{
+    links::resolve, links::Url, parsers::link_href, parsers::ListSelectors, parsers::Parsed, Error,
+};
+use scraper::Html;
 // parse month urls
-pub fn parse_year_page(content: String) -> Result<Vec<String>, Error> {
-    let mut results = Vec::new();
+pub fn parse_year_page(
+    content: String,
+    url: &Url,
+    selectors: &ListSelectors,
+) -> Result<Parsed<Url>, Error> {
+    let mut results = Parsed::new(url);
 
     let structure = Html::parse_document(&content);
 
-    let li_selector = Selector::parse("li.list-group-item")
-        .map_err(|_| Error::ParsingError("li selector".to_string()))?;
-
-    for each in structure.select(&li_selector) {
-        let a_selector =
-            Selector::parse("a").map_err(|_| Error::ParsingError("link selector".to_string()))?;
-        let link = each
-            .select(&a_selector)
-            .next()
-            .ok_or(Error::ParsingError("link".to_string()))?;
-        results.push(format!(
-            "{}{}",
-            ROOT,
-            link.value()
-                .attr("href")
-                .ok_or(Error::ParsingError("link href".to_string()))?
-                .to_string(),
-        ));
+    for each in structure.select(&selectors.item) {
+        let link = link_href(each, &selectors.link).and_then(|href| resolve(url, href));
+        results.push(each, link)?;
     }
 
-    Ok(results)
+    results.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::links::SITE;
+    use crate::parsers::Selectors;
+
+    #[test]
+    fn parses_month_urls() {
+        let content = include_str!("../../fixtures/year.html").to_string();
+        let url = SITE.join("1974/").unwrap();
+        let parsed = parse_year_page(content, &url, &Selectors::default().year).unwrap();
+        let urls: Vec<&str> = parsed.items.iter().map(Url::as_str).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://cn.govopendata.com/renminribao/1974/1/",
+                "https://cn.govopendata.com/renminribao/1974/02/",
+                "https://cn.govopendata.com/renminribao/1974/11/",
+                "https://cn.govopendata.com/renminribao/1974/12",
+            ]
+        );
+    }
 }
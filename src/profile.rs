@@ -0,0 +1,289 @@
+use crate::{load_keyword_list, Date, Entity, FileSource};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// settings of a crawl, every one optional so that profiles can be layered:
+// the defaults of the file, then the named profile, then command line flags
+#[derive(serde::Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    pub keywords: Option<Vec<String>>,
+    // keyword groups, e.g. {name: HUST, aliases: [华中工学院, {keyword: 华中科技大学, from: 2000-05-26}]}
+    pub entities: Option<Vec<Entity>>,
+    // a keyword list, CSV or YAML, read into the keyword groups of the same layer;
+    // relative to the profile file, see load_keyword_list
+    pub keyword_file: Option<PathBuf>,
+    // OCR noise in titles and its replacement, e.g. {"＠": ""}
+    pub ocr_substitutions: Option<BTreeMap<String, String>>,
+    // edits tolerated when a keyword is not found as is; hits then carry a confidence below 1
+    pub max_edits: Option<usize>,
+    // issue dates to search, e.g. "1952-01-01"; a year or month given as to stands for its
+    // last day
+    pub from: Option<Date>,
+    #[serde(deserialize_with = "crate::date::deserialize_last")]
+    pub to: Option<Date>,
+    pub pages: Option<Vec<u32>>,
+    pub lenient: Option<bool>,
+    pub concurrency: Option<usize>,
+    pub retries: Option<u32>,
+    // requests started per second
+    pub rate_limit: Option<f64>,
+    pub resume: Option<bool>,
+    // keep every scanned title, to match them again with `webb rematch`
+    pub corpus: Option<bool>,
+    // directory of results, report, visited pages and logs
+    pub output: Option<PathBuf>,
+    // css selectors of the archive markup, e.g. site.yaml
+    pub site: Option<PathBuf>,
+    pub dashboard: Option<bool>,
+    pub control_address: Option<String>,
+    pub metrics_address: Option<String>,
+    pub log_level: Option<String>,
+    pub fetch_log_level: Option<String>,
+    pub matching_log_level: Option<String>,
+    pub log_json: Option<bool>,
+}
+
+// a profile file: shared defaults plus named profiles
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profiles {
+    pub defaults: Profile,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+fn key(prefix: &str, name: &str) -> String {
+    match prefix {
+        "" => format!("--{}", name),
+        prefix => format!("{}.{}", prefix, name),
+    }
+}
+
+fn replace<T>(value: &mut Option<T>, layer: Option<T>) {
+    if layer.is_some() {
+        *value = layer;
+    }
+}
+
+impl Profile {
+    // settings of layer win over those of self
+    pub fn merge(mut self, layer: Profile) -> Profile {
+        replace(&mut self.keywords, layer.keywords);
+        replace(&mut self.entities, layer.entities);
+        replace(&mut self.keyword_file, layer.keyword_file);
+        replace(&mut self.ocr_substitutions, layer.ocr_substitutions);
+        replace(&mut self.max_edits, layer.max_edits);
+        replace(&mut self.from, layer.from);
+        replace(&mut self.to, layer.to);
+        replace(&mut self.pages, layer.pages);
+        replace(&mut self.lenient, layer.lenient);
+        replace(&mut self.concurrency, layer.concurrency);
+        replace(&mut self.retries, layer.retries);
+        replace(&mut self.rate_limit, layer.rate_limit);
+        replace(&mut self.resume, layer.resume);
+        replace(&mut self.corpus, layer.corpus);
+        replace(&mut self.output, layer.output);
+        replace(&mut self.site, layer.site);
+        replace(&mut self.dashboard, layer.dashboard);
+        replace(&mut self.control_address, layer.control_address);
+        replace(&mut self.metrics_address, layer.metrics_address);
+        replace(&mut self.log_level, layer.log_level);
+        replace(&mut self.fetch_log_level, layer.fetch_log_level);
+        replace(&mut self.matching_log_level, layer.matching_log_level);
+        replace(&mut self.log_json, layer.log_json);
+        self
+    }
+
+    // the keyword groups of the keyword file of this layer after those listed inline,
+    // a relative file being found in directory
+    pub fn read_keyword_file(mut self, directory: &Path, prefix: &str) -> Result<Profile, String> {
+        if let Some(file) = &self.keyword_file {
+            let listed = load_keyword_list(&directory.join(file))
+                .map_err(|error| format!("{}: {}", key(prefix, "keyword-file"), error))?;
+            self.entities.get_or_insert_with(Vec::new).extend(listed);
+        }
+        Ok(self)
+    }
+
+    // check the values set in this layer, naming keys below prefix, e.g. "profiles.hust";
+    // an empty prefix names the command line flags instead
+    pub fn validate(&self, prefix: &str) -> Result<(), String> {
+        let key = |name: &str| key(prefix, name);
+        if self
+            .keywords
+            .as_ref()
+            .is_some_and(|keywords| keywords.iter().all(|keyword| keyword.trim().is_empty()))
+        {
+            return Err(format!("{}: lists no keywords", key("keywords")));
+        }
+        for entity in self.entities.iter().flatten() {
+            entity
+                .check()
+                .map_err(|error| format!("{}: {}", key("entities"), error))?;
+        }
+        if self
+            .ocr_substitutions
+            .as_ref()
+            .is_some_and(|substitutions| substitutions.keys().any(|noise| noise.trim().is_empty()))
+        {
+            return Err(format!(
+                "{}: empty text to replace",
+                key("ocr-substitutions")
+            ));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(format!("{}: {} is before from ({})", key("to"), to, from));
+            }
+        }
+        if self.pages.as_ref().is_some_and(|pages| pages.contains(&0)) {
+            return Err(format!("{}: pages are numbered from 1", key("pages")));
+        }
+        if self.concurrency == Some(0) {
+            return Err(format!("{}: must be at least 1", key("concurrency")));
+        }
+        if self.retries == Some(0) {
+            return Err(format!("{}: must be at least 1", key("retries")));
+        }
+        if self
+            .rate_limit
+            .is_some_and(|rate| !rate.is_finite() || rate <= 0.0)
+        {
+            return Err(format!("{}: must be a number above 0", key("rate-limit")));
+        }
+        Ok(())
+    }
+}
+
+impl Profiles {
+    pub fn load(source: &FileSource) -> Result<Profiles, String> {
+        let directory = source.path().parent().unwrap_or(Path::new("."));
+        let Profiles { defaults, profiles } = source.try_load::<Profiles>()?;
+        Profiles {
+            defaults: defaults.read_keyword_file(directory, "defaults")?,
+            profiles: profiles
+                .into_iter()
+                .map(|(name, profile)| {
+                    let prefix = format!("profiles.{}", name);
+                    Ok((name, profile.read_keyword_file(directory, &prefix)?))
+                })
+                .collect::<Result<_, String>>()?,
+        }
+        .check()
+    }
+
+    fn check(self) -> Result<Profiles, String> {
+        self.defaults.validate("defaults")?;
+        for (name, profile) in &self.profiles {
+            profile.validate(&format!("profiles.{}", name))?;
+        }
+        Ok(self)
+    }
+
+    // the defaults with the named profile on top, checked once more as a whole
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile, String> {
+        let Some(name) = name else {
+            return Ok(self.defaults.clone());
+        };
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            format!(
+                "no profile named {}, expected one of: {}",
+                name,
+                self.profiles
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+        })?;
+        let merged = self.defaults.clone().merge(profile.clone());
+        merged.validate(&format!("profiles.{}", name))?;
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // tests run in parallel, so every call writes a file of its own
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+
+    fn parse(yaml: &str) -> Result<Profiles, String> {
+        let file = std::env::temp_dir().join(format!(
+            "webb-profile-{}-{}.yaml",
+            std::process::id(),
+            FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&file, yaml).unwrap();
+        let profiles = Profiles::load(&FileSource::new(&file));
+        std::fs::remove_file(&file).unwrap();
+        let prefix = format!("{}: ", file.to_string_lossy());
+        profiles.map_err(|error| error.strip_prefix(&prefix).unwrap_or(&error).to_string())
+    }
+
+    #[test]
+    fn layers_profile_over_defaults() {
+        let profiles = parse(
+            "defaults:\n  concurrency: 4\n  pages: [1]\n\
+             profiles:\n  hust-history:\n    keywords: [华中工学院]\n    from: 1952-01-01\n    to: 1966-12-31\n    pages: [1, 2]\n",
+        )
+        .unwrap();
+        let profile = profiles.profile(Some("hust-history")).unwrap();
+        assert_eq!(profile.concurrency, Some(4));
+        assert_eq!(profile.pages, Some(vec![1, 2]));
+        assert_eq!(profile.from, Some(Date::new(1952, 1, 1)));
+        let overridden = profile.merge(Profile {
+            concurrency: Some(1),
+            ..Profile::default()
+        });
+        assert_eq!(overridden.concurrency, Some(1));
+        assert_eq!(overridden.keywords, Some(vec!["华中工学院".to_string()]));
+        let profiles =
+            parse("profiles:\n  sixties:\n    from: \"1960\"\n    to: \"1966\"\n").unwrap();
+        let profile = profiles.profile(Some("sixties")).unwrap();
+        assert_eq!(profile.from, Some(Date::new(1960, 1, 1)));
+        assert_eq!(profile.to, Some(Date::new(1966, 12, 31)));
+    }
+
+    #[test]
+    fn reads_keyword_groups() {
+        let profiles = parse(
+            "profiles:\n  hust:\n    entities:\n      - name: HUST\n        aliases:\n          - 华中工学院\n          - {keyword: 华中科技大学, from: 2000-05-26}\n",
+        )
+        .unwrap();
+        let entities = profiles.profile(Some("hust")).unwrap().entities.unwrap();
+        assert_eq!(entities[0].name, "HUST");
+        assert_eq!(entities[0].aliases[1].from, Some(Date::new(2000, 5, 26)));
+        assert!(
+            parse("profiles:\n  hust:\n    keyword-file: webb-no-such-keywords.csv\n")
+                .unwrap_err()
+                .starts_with("profiles.hust.keyword-file: ")
+        );
+        assert_eq!(
+            parse("defaults:\n  entities:\n    - name: HUST\n      aliases: []\n").unwrap_err(),
+            "defaults.entities: HUST: lists no aliases"
+        );
+    }
+
+    #[test]
+    fn errors_point_to_the_key() {
+        let error = |yaml: &str| parse(yaml).unwrap_err();
+        assert!(error("profiles:\n  hust:\n    keyword: [a]\n")
+            .starts_with("profiles.hust.keyword: unknown field `keyword`"));
+        assert!(error("profiles:\n  hust:\n    from: 1958-13-01\n")
+            .starts_with("profiles.hust.from: invalid date"));
+        assert!(error("defaults:\n  concurrency: many\n")
+            .starts_with("defaults.concurrency: invalid type"));
+        assert_eq!(
+            error("profiles:\n  hust:\n    from: 1960-01-01\n    to: 1958-01-01\n"),
+            "profiles.hust.to: 1958-01-01 is before from (1960-01-01)"
+        );
+        let profiles = parse("profiles:\n  hust:\n    concurrency: 2\n").unwrap();
+        assert!(profiles
+            .profile(Some("tongji"))
+            .unwrap_err()
+            .starts_with("no profile named tongji"));
+    }
+}
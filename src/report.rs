@@ -0,0 +1,544 @@
+use crate::{
+    CrawlResults, Date, Event, FailCategory, LogExpect, Sink, SuccessTarget, TargetType, Url,
+};
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CHART_WIDTH: usize = 720;
+const CHART_HEIGHT: usize = 240;
+const CHART_MARGIN: usize = 32;
+
+// counted while the crawl runs, the rest comes from the results
+struct Tally {
+    started: Instant,
+    // articles listed on handled pages, by year and month
+    scanned: BTreeMap<(u32, u32), u64>,
+    pages: u64,
+    bytes: u64,
+}
+
+// a summary of a finished run, written as <stem>.md, <stem>.html and <stem>.svg
+pub struct Report {
+    root: Url,
+    stem: PathBuf,
+    tally: Mutex<Tally>,
+}
+
+#[derive(Default)]
+struct Period {
+    scanned: u64,
+    hits: u64,
+}
+
+// everything the report shows, gathered once the run is over
+struct Summary<'a> {
+    duration: Duration,
+    pages: u64,
+    bytes: u64,
+    months: BTreeMap<(u32, u32), Period>,
+    years: BTreeMap<u32, Period>,
+    // hits per entity, across all of its names
+    entities: BTreeMap<&'a str, u64>,
+    keywords: BTreeMap<&'a str, u64>,
+    failures: BTreeMap<(TargetType, FailCategory), u64>,
+    hits: Vec<(Option<Date>, &'a SuccessTarget)>,
+    // titles with a keyword ruled out by one of its rules
+    excluded: usize,
+}
+
+impl Summary<'_> {
+    fn scanned(&self) -> u64 {
+        self.years.values().map(|year| year.scanned).sum()
+    }
+
+    fn per_second(&self, count: u64) -> f64 {
+        match self.duration.as_secs_f64() {
+            seconds if seconds > 0.0 => count as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    fn overview(&self) -> Vec<(&'static str, String)> {
+        let seconds = self.duration.as_secs();
+        vec![
+            (
+                "Duration",
+                format!(
+                    "{:02}:{:02}:{:02}",
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                ),
+            ),
+            (
+                "Pages fetched",
+                format!("{} ({:.2}/s)", self.pages, self.per_second(self.pages)),
+            ),
+            (
+                "Data fetched",
+                format!("{:.1} MiB", self.bytes as f64 / 1048576.0),
+            ),
+            (
+                "Articles scanned",
+                format!(
+                    "{} ({:.2}/s)",
+                    self.scanned(),
+                    self.per_second(self.scanned())
+                ),
+            ),
+            ("Hits", self.hits.len().to_string()),
+            (
+                "Fuzzy hits to review",
+                self.hits
+                    .iter()
+                    .filter(|(_, hit)| confidence(hit) < 1.0)
+                    .count()
+                    .to_string(),
+            ),
+            ("Ruled out", self.excluded.to_string()),
+            ("Failures", self.failures.values().sum::<u64>().to_string()),
+        ]
+    }
+}
+
+impl Report {
+    // root is the archive index, to tell the dates of pages and articles
+    pub fn new(root: Url, stem: impl Into<PathBuf>) -> Self {
+        Report {
+            root,
+            stem: stem.into(),
+            tally: Mutex::new(Tally {
+                started: Instant::now(),
+                scanned: BTreeMap::new(),
+                pages: 0,
+                bytes: 0,
+            }),
+        }
+    }
+
+    fn date(&self, url: &str) -> Option<Date> {
+        Url::parse(url)
+            .ok()
+            .and_then(|url| Date::from_url(&self.root, &url))
+    }
+
+    fn summarize<'a>(&self, results: &'a CrawlResults) -> Summary<'a> {
+        let tally = self.tally.lock().log_expect("Failed to aquire lock.");
+        let mut months: BTreeMap<(u32, u32), Period> = BTreeMap::new();
+        for (month, scanned) in &tally.scanned {
+            months.entry(*month).or_default().scanned += scanned;
+        }
+        let mut entities = BTreeMap::new();
+        let mut keywords = BTreeMap::new();
+        let mut hits = Vec::new();
+        for hit in &results.successes {
+            let date = self.date(&hit.url);
+            if let Some(date) = date {
+                months.entry((date.year, date.month)).or_default().hits += 1;
+            }
+            *entities
+                .entry(hit.entity.as_deref().unwrap_or("unknown"))
+                .or_insert(0) += 1;
+            *keywords
+                .entry(hit.keyword.as_deref().unwrap_or("unknown"))
+                .or_insert(0) += 1;
+            hits.push((date, hit));
+        }
+        hits.sort_by_key(|(date, _)| *date);
+        let mut years: BTreeMap<u32, Period> = BTreeMap::new();
+        for ((year, _), period) in &months {
+            let total = years.entry(*year).or_default();
+            total.scanned += period.scanned;
+            total.hits += period.hits;
+        }
+        let mut failures = BTreeMap::new();
+        for failure in &results.failures {
+            *failures
+                .entry((failure.target_type, failure.category))
+                .or_insert(0) += 1;
+        }
+        Summary {
+            duration: tally.started.elapsed(),
+            pages: tally.pages,
+            bytes: tally.bytes,
+            months,
+            years,
+            entities,
+            keywords,
+            failures,
+            hits,
+            excluded: results.excluded.len(),
+        }
+    }
+
+    fn path(&self, extension: &str) -> PathBuf {
+        self.stem.with_extension(extension)
+    }
+
+    fn write(&self, extension: &str, content: String) {
+        let path = self.path(extension);
+        match std::fs::write(&path, content) {
+            Ok(()) => info!("Report saved to {}.", path.to_string_lossy()),
+            Err(error) => warn!(
+                "Failed to save report to {}: {}.",
+                path.to_string_lossy(),
+                error
+            ),
+        }
+    }
+}
+
+impl Sink for Report {
+    fn event(&self, event: &Event) {
+        let mut tally = self.tally.lock().log_expect("Failed to aquire lock.");
+        match event {
+            Event::PageFetched { bytes, .. } => {
+                tally.pages += 1;
+                tally.bytes += *bytes as u64;
+            }
+            Event::LevelExpanded {
+                url,
+                level: TargetType::Page,
+                articles,
+                ..
+            } => {
+                if let Some(date) = self.date(url) {
+                    *tally.scanned.entry((date.year, date.month)).or_insert(0) += *articles as u64;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(&self, results: &CrawlResults) {
+        let summary = self.summarize(results);
+        let chart = chart(&summary);
+        let chart_name = self
+            .path("svg")
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.write("md", markdown(&summary, &chart_name));
+        self.write("html", html(&summary, &chart));
+        self.write("svg", chart);
+    }
+}
+
+fn confidence(hit: &SuccessTarget) -> f64 {
+    hit.confidence.unwrap_or(1.0)
+}
+
+// how sure the match is, e.g. "exact" or "~80%"
+fn match_cell(hit: &SuccessTarget) -> String {
+    match confidence(hit) {
+        confidence if confidence < 1.0 => format!("~{:.0}%", confidence * 100.0),
+        _ => "exact".to_string(),
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn markdown(summary: &Summary, chart: &str) -> String {
+    let mut out = String::from("# Crawl report\n\n| | |\n|---|---|\n");
+    for (name, value) in summary.overview() {
+        let _ = writeln!(out, "| {} | {} |", name, value);
+    }
+    let _ = write!(out, "\n## Hits per year\n\n![Hits per year]({})\n\n", chart);
+    out.push_str("| Year | Articles scanned | Hits |\n|---|---:|---:|\n");
+    for (year, period) in &summary.years {
+        let _ = writeln!(out, "| {} | {} | {} |", year, period.scanned, period.hits);
+    }
+    out.push_str("\n## Per month\n\n| Month | Articles scanned | Hits |\n|---|---:|---:|\n");
+    for ((year, month), period) in &summary.months {
+        let _ = writeln!(
+            out,
+            "| {:04}-{:02} | {} | {} |",
+            year, month, period.scanned, period.hits
+        );
+    }
+    out.push_str("\n## Hits per entity\n\n| Entity | Hits |\n|---|---:|\n");
+    for (entity, hits) in &summary.entities {
+        let _ = writeln!(out, "| {} | {} |", markdown_cell(entity), hits);
+    }
+    out.push_str("\n## Hits per keyword\n\n| Keyword | Hits |\n|---|---:|\n");
+    for (keyword, hits) in &summary.keywords {
+        let _ = writeln!(out, "| {} | {} |", markdown_cell(keyword), hits);
+    }
+    out.push_str("\n## Failures\n\n| Level | Reason | Count |\n|---|---|---:|\n");
+    for ((level, category), count) in &summary.failures {
+        let _ = writeln!(out, "| {:?} | {:?} | {} |", level, category, count);
+    }
+    out.push_str(
+        "\n## Hits\n\n| Date | Page | Title | Entity | Keyword | Match |\n|---|---|---|---|---|---|\n",
+    );
+    for (date, hit) in &summary.hits {
+        let _ = writeln!(
+            out,
+            "| {} | {} | [{}]({}) | {} | {} | {} |",
+            date.map(|date| date.to_string()).unwrap_or_default(),
+            markdown_cell(hit.page.as_deref().unwrap_or("")),
+            markdown_cell(hit.title.trim()),
+            hit.url,
+            markdown_cell(hit.entity.as_deref().unwrap_or("")),
+            markdown_cell(hit.keyword.as_deref().unwrap_or("")),
+            match_cell(hit)
+        );
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html_table(out: &mut String, head: &[&str], rows: impl IntoIterator<Item = Vec<String>>) {
+    out.push_str("<table>\n<tr>");
+    for cell in head {
+        let _ = write!(out, "<th>{}</th>", cell);
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for cell in row {
+            let _ = write!(out, "<td>{}</td>", cell);
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+fn html(summary: &Summary, chart: &str) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Crawl report</title>\n\
+         <style>body{font-family:sans-serif;max-width:60em;margin:auto}\
+         table{border-collapse:collapse;margin-bottom:1em}\
+         th,td{border:1px solid #ccc;padding:2px 8px;text-align:left}</style>\n\
+         </head>\n<body>\n<h1>Crawl report</h1>\n",
+    );
+    html_table(
+        &mut out,
+        &["", ""],
+        summary
+            .overview()
+            .into_iter()
+            .map(|(name, value)| vec![name.to_string(), escape(&value)]),
+    );
+    out.push_str("<h2>Hits per year</h2>\n");
+    out.push_str(chart);
+    out.push('\n');
+    html_table(
+        &mut out,
+        &["Year", "Articles scanned", "Hits"],
+        summary.years.iter().map(|(year, period)| {
+            vec![
+                year.to_string(),
+                period.scanned.to_string(),
+                period.hits.to_string(),
+            ]
+        }),
+    );
+    out.push_str("<h2>Per month</h2>\n");
+    html_table(
+        &mut out,
+        &["Month", "Articles scanned", "Hits"],
+        summary.months.iter().map(|((year, month), period)| {
+            vec![
+                format!("{:04}-{:02}", year, month),
+                period.scanned.to_string(),
+                period.hits.to_string(),
+            ]
+        }),
+    );
+    out.push_str("<h2>Hits per entity</h2>\n");
+    html_table(
+        &mut out,
+        &["Entity", "Hits"],
+        summary
+            .entities
+            .iter()
+            .map(|(entity, hits)| vec![escape(entity), hits.to_string()]),
+    );
+    out.push_str("<h2>Hits per keyword</h2>\n");
+    html_table(
+        &mut out,
+        &["Keyword", "Hits"],
+        summary
+            .keywords
+            .iter()
+            .map(|(keyword, hits)| vec![escape(keyword), hits.to_string()]),
+    );
+    out.push_str("<h2>Failures</h2>\n");
+    html_table(
+        &mut out,
+        &["Level", "Reason", "Count"],
+        summary.failures.iter().map(|((level, category), count)| {
+            vec![
+                format!("{:?}", level),
+                format!("{:?}", category),
+                count.to_string(),
+            ]
+        }),
+    );
+    out.push_str("<h2>Hits</h2>\n");
+    html_table(
+        &mut out,
+        &["Date", "Page", "Title", "Entity", "Keyword", "Match"],
+        summary.hits.iter().map(|(date, hit)| {
+            vec![
+                date.map(|date| date.to_string()).unwrap_or_default(),
+                escape(hit.page.as_deref().unwrap_or("")),
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(&hit.url),
+                    escape(hit.title.trim())
+                ),
+                escape(hit.entity.as_deref().unwrap_or("")),
+                escape(hit.keyword.as_deref().unwrap_or("")),
+                match_cell(hit),
+            ]
+        }),
+    );
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+// a bar chart of hits per year, covering every year from the first to the last seen
+fn chart(summary: &Summary) -> String {
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"10\">\n",
+        CHART_WIDTH, CHART_HEIGHT
+    );
+    let (first, last) = match (
+        summary.years.keys().next(),
+        summary.years.keys().next_back(),
+    ) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => {
+            out.push_str("<text x=\"10\" y=\"20\">No years scanned.</text>\n</svg>\n");
+            return out;
+        }
+    };
+    let most = summary
+        .years
+        .values()
+        .map(|year| year.hits)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let years = (last - first + 1) as usize;
+    let plot_width = CHART_WIDTH - 2 * CHART_MARGIN;
+    let plot_height = CHART_HEIGHT - 2 * CHART_MARGIN;
+    let step = plot_width as f64 / years as f64;
+    // label about every 40 pixels
+    let label_every = (40.0 / step).ceil().max(1.0) as usize;
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\">{} hit(s)</text>",
+        CHART_MARGIN,
+        CHART_MARGIN - 8,
+        most
+    );
+    let _ = writeln!(
+        out,
+        "<line x1=\"{m}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#333\"/>",
+        m = CHART_MARGIN,
+        b = CHART_HEIGHT - CHART_MARGIN,
+        r = CHART_WIDTH - CHART_MARGIN
+    );
+    for (index, year) in (first..=last).enumerate() {
+        let hits = summary.years.get(&year).map_or(0, |period| period.hits);
+        let height = hits as f64 * plot_height as f64 / most as f64;
+        let x = CHART_MARGIN as f64 + index as f64 * step;
+        let _ = writeln!(
+            out,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#4a7ebb\"><title>{}: {}</title></rect>",
+            x + step * 0.1,
+            (CHART_HEIGHT - CHART_MARGIN) as f64 - height,
+            step * 0.8,
+            height,
+            year,
+            hits
+        );
+        if index % label_every == 0 {
+            let _ = writeln!(
+                out,
+                "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                x + step / 2.0,
+                CHART_HEIGHT - CHART_MARGIN + 14,
+                year
+            );
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{links::SITE, Visited};
+
+    fn hit(path: &str, title: &str, keyword: &str) -> SuccessTarget {
+        SuccessTarget {
+            title: title.to_string(),
+            url: SITE.join(path).unwrap().to_string(),
+            page: Some("第1版".to_string()),
+            page_number: Some(1),
+            keyword: Some(keyword.to_string()),
+            entity: Some("HUST".to_string()),
+            confidence: Some(1.0),
+            category: None,
+            row: None,
+        }
+    }
+
+    #[test]
+    fn summarizes_per_year_and_month() {
+        let report = Report::new(SITE.clone(), "report");
+        for (path, articles) in [("1958/3/5/1/", 10), ("1958/4/1/1/", 5), ("1960/1/2/1/", 7)] {
+            report.event(&Event::LevelExpanded {
+                url: SITE.join(path).unwrap().to_string(),
+                level: TargetType::Page,
+                pages: 0,
+                articles,
+            });
+        }
+        let results = CrawlResults {
+            excluded: Vec::new(),
+            successes: vec![
+                hit("1958/3/5/1/#1", "华中工学院 | 开学", "华中工学院"),
+                hit("1960/1/2/1/#2", "华中理工大学", "华中理工大学"),
+            ],
+            failures: Vec::new(),
+            warnings: Vec::new(),
+            visited: Visited::new(),
+            corpus: Vec::new(),
+            count: 22,
+            articles: 22,
+        };
+        let summary = report.summarize(&results);
+        assert_eq!(summary.scanned(), 22);
+        assert_eq!(summary.years[&1958].scanned, 15);
+        assert_eq!(summary.years[&1958].hits, 1);
+        assert_eq!(summary.months[&(1958, 4)].hits, 0);
+        assert_eq!(summary.keywords["华中工学院"], 1);
+        assert_eq!(summary.entities["HUST"], 2);
+
+        let markdown = markdown(&summary, "report.svg");
+        assert!(markdown.contains("| 1960 | 7 | 1 |"));
+        assert!(markdown.contains("| 1958-03 | 10 | 1 |"));
+        assert!(markdown.contains("华中工学院 \\| 开学"));
+        // 1959 has no hits but still gets a slot
+        let chart = chart(&summary);
+        assert_eq!(chart.matches("<rect").count(), 3);
+    }
+}
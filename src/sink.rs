@@ -0,0 +1,135 @@
+use crate::{CrawlResults, Event, FileSource, SuccessTarget};
+use log::info;
+use tokio::sync::mpsc::UnboundedSender;
+
+// receives events while the crawl runs and the results once it is over
+pub trait Sink: Send + Sync {
+    fn event(&self, _event: &Event) {}
+
+    fn finish(&self, _results: &CrawlResults) {}
+}
+
+// forwards the events picked by select to a channel, see Crawler::events and Crawler::hits
+pub struct ChannelSink<T> {
+    tx: UnboundedSender<T>,
+    select: fn(&Event) -> Option<T>,
+}
+
+impl<T> ChannelSink<T> {
+    pub fn new(tx: UnboundedSender<T>, select: fn(&Event) -> Option<T>) -> Self {
+        ChannelSink { tx, select }
+    }
+}
+
+impl<T: Send> Sink for ChannelSink<T> {
+    fn event(&self, event: &Event) {
+        if let Some(item) = (self.select)(event) {
+            // nobody listening any more is fine
+            let _ = self.tx.send(item);
+        }
+    }
+}
+
+// stores the result lists as yaml files, keeping backups of earlier runs
+pub struct YamlSink {
+    successes: FileSource,
+    failures: Option<FileSource>,
+    warnings: Option<FileSource>,
+    visited: Option<FileSource>,
+    review: Option<FileSource>,
+    excluded: Option<FileSource>,
+    corpus: Option<FileSource>,
+}
+
+impl YamlSink {
+    pub fn new(successes: &str, failures: &str, warnings: &str) -> Self {
+        YamlSink {
+            failures: Some(FileSource::new(failures)),
+            warnings: Some(FileSource::new(warnings)),
+            ..YamlSink::hits(successes)
+        }
+    }
+
+    // only the hits, without failed pages and parse warnings, e.g. for a rematch,
+    // which requests no pages
+    pub fn hits(successes: &str) -> Self {
+        YamlSink {
+            successes: FileSource::new(successes),
+            failures: None,
+            warnings: None,
+            visited: None,
+            review: None,
+            excluded: None,
+            corpus: None,
+        }
+    }
+
+    // also record completed pages so a later run can resume
+    pub fn visited(mut self, visited: &str) -> Self {
+        self.visited = Some(FileSource::new(visited));
+        self
+    }
+
+    // also list fuzzy hits on their own, to be checked by hand
+    pub fn review(mut self, review: &str) -> Self {
+        self.review = Some(FileSource::new(review));
+        self
+    }
+
+    // also list titles whose keyword a rule ruled out, to be checked by hand
+    pub fn excluded(mut self, excluded: &str) -> Self {
+        self.excluded = Some(FileSource::new(excluded));
+        self
+    }
+
+    // also store every title examined, if the crawl kept them, for a later rematch
+    pub fn corpus(mut self, corpus: &str) -> Self {
+        self.corpus = Some(FileSource::new(corpus));
+        self
+    }
+}
+
+impl Sink for YamlSink {
+    fn finish(&self, results: &CrawlResults) {
+        self.successes.store(&results.successes);
+        if let Some(failures) = &self.failures {
+            failures.store(&results.failures);
+        }
+        if let Some(warnings) = &self.warnings {
+            if !results.warnings.is_empty() {
+                info!("Skipped {} malformed item(s).", results.warnings.len());
+                warnings.store(&results.warnings);
+            }
+        }
+        if let Some(review) = &self.review {
+            let fuzzy: Vec<&SuccessTarget> = results
+                .successes
+                .iter()
+                .filter(|hit| hit.confidence.is_some_and(|confidence| confidence < 1.0))
+                .collect();
+            if !fuzzy.is_empty() {
+                info!("{} fuzzy hit(s) to review.", fuzzy.len());
+                review.store(&fuzzy);
+            }
+        }
+        if let Some(excluded) = &self.excluded {
+            if !results.excluded.is_empty() {
+                info!(
+                    "Ruled out {} title(s) with a keyword.",
+                    results.excluded.len()
+                );
+                excluded.store(&results.excluded);
+            }
+        }
+        if let Some(corpus) = &self.corpus {
+            if !results.corpus.is_empty() {
+                info!("Kept {} title(s) to match again.", results.corpus.len());
+                corpus.store(&results.corpus);
+            }
+        }
+        if let Some(visited) = &self.visited {
+            results.visited.store(visited);
+        }
+        info!("File saved.");
+    }
+}
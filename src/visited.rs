@@ -0,0 +1,70 @@
+use crate::{links::Url, FileSource};
+use log::{info, trace};
+use std::collections::{BTreeSet, HashSet};
+
+// canonical urls seen during the crawl, so every page and article is handled once
+#[derive(Default)]
+pub struct Visited {
+    pages: HashSet<String>,
+    // leaf pages that were fully examined, the only ones worth skipping in a later run
+    completed: BTreeSet<String>,
+    // pages completed by the run being resumed, not yet linked in this one
+    resumed: HashSet<String>,
+    articles: HashSet<String>,
+    // pages skipped because the run being resumed completed them
+    pub resumed_pages: u64,
+    pub duplicate_pages: u64,
+    pub duplicate_articles: u64,
+}
+
+impl Visited {
+    pub fn new() -> Self {
+        Visited::default()
+    }
+
+    pub fn load(&mut self, source: &FileSource) {
+        if let Some(completed) = source.load::<BTreeSet<String>>() {
+            info!("Resuming with {} completed page(s).", completed.len());
+            self.resumed.extend(completed.iter().cloned());
+            self.completed = completed;
+        }
+    }
+
+    pub fn store(&self, source: &FileSource) {
+        source.store(&self.completed);
+    }
+
+    // returns false if the page has already been scheduled
+    pub fn visit(&mut self, url: &Url) -> bool {
+        let url = url.to_string();
+        if self.resumed.remove(&url) {
+            trace!("Skipping page {} completed before.", url);
+            self.resumed_pages += 1;
+            self.pages.insert(url);
+            return false;
+        }
+        if self.pages.insert(url.clone()) {
+            return true;
+        }
+        trace!("Skipping duplicate page {}.", url);
+        self.duplicate_pages += 1;
+        false
+    }
+
+    pub fn complete(&mut self, url: &Url) {
+        self.completed.insert(url.to_string());
+    }
+
+    // returns false if an article with the same id has already been examined
+    pub fn examine(&mut self, article_url: &str) -> bool {
+        let id = article_url
+            .rsplit_once('#')
+            .map_or(article_url, |(_, id)| id);
+        if self.articles.insert(id.to_string()) {
+            return true;
+        }
+        trace!("Skipping duplicate article {}.", article_url);
+        self.duplicate_articles += 1;
+        false
+    }
+}
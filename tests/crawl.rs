@@ -0,0 +1,698 @@
+use hust_news_entry::{
+    crawl, Alias, Config, Control, CrawlResults, Crawler, Date, DateRange, Entity, Event,
+    FailCategory, FileSource, Metrics, Profiles, ScannedTitle, Sink, TargetType, Url, Visited,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const CLIENT_TIMEOUT: Duration = Duration::from_millis(300);
+
+// how the mock archive answers a path
+enum Response {
+    Page(String),
+    Status(u16),
+    // 429 Too Many Requests, asking to wait this many seconds
+    Throttled(u64),
+    Hang,
+}
+
+// a synthetic archive served over plain HTTP on a local port
+struct MockArchive {
+    root: Url,
+}
+
+impl MockArchive {
+    async fn start(routes: HashMap<&'static str, Response>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let routes = Arc::new(routes);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(serve(stream, Arc::clone(&routes)));
+            }
+        });
+        MockArchive {
+            root: Url::parse(&format!("http://{}/renminribao/", address)).unwrap(),
+        }
+    }
+}
+
+async fn serve(mut stream: TcpStream, routes: Arc<HashMap<&'static str, Response>>) {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let mut headers = String::new();
+    let (status, body) = match routes.get(path) {
+        Some(Response::Page(body)) => (200, body.clone()),
+        Some(Response::Status(status)) => (*status, String::new()),
+        Some(Response::Throttled(seconds)) => {
+            headers = format!("Retry-After: {}\r\n", seconds);
+            (429, String::new())
+        }
+        Some(Response::Hang) => {
+            tokio::time::sleep(CLIENT_TIMEOUT * 10).await;
+            return;
+        }
+        None => (404, String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        headers,
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+fn listing(hrefs: &[&str]) -> Response {
+    let items: String = hrefs
+        .iter()
+        .map(|href| {
+            format!(
+                "<li class=\"list-group-item\"><a href=\"{}\">{}</a></li>",
+                href, href
+            )
+        })
+        .collect();
+    Response::Page(format!("<html><body><ul>{}</ul></body></html>", items))
+}
+
+fn day(articles: &[(&str, &str)]) -> Response {
+    let items: String = articles
+        .iter()
+        .map(|(href, title)| {
+            format!(
+                "<p class=\"card-text\"><a href=\"{}\">{}</a></p>",
+                href, title
+            )
+        })
+        .collect();
+    Response::Page(format!(
+        "<html><body><div class=\"card\">{}</div></body></html>",
+        items
+    ))
+}
+
+fn page(heading: &str, articles: &[(&str, &str)]) -> Response {
+    let cards: String = articles
+        .iter()
+        .map(|(id, title)| {
+            format!(
+                "<div class=\"card\"><h5><a id=\"{}\">{}</a></h5><p class=\"card-text\">…</p></div>",
+                id, title
+            )
+        })
+        .collect();
+    Response::Page(format!(
+        "<html><body><h1>{}</h1>{}</body></html>",
+        heading, cards
+    ))
+}
+
+fn archive() -> HashMap<&'static str, Response> {
+    HashMap::from([
+        ("/renminribao/", listing(&["1958/", "1959/"])),
+        ("/renminribao/1958/", listing(&["3/", "4/"])),
+        // served only as linked, zero-padded like the month listings of the site
+        ("/renminribao/1958/3/", listing(&["05/"])),
+        (
+            "/renminribao/1958/3/05/",
+            day(&[
+                ("7/#188965", "华中工学院反浪费反保守做得好"),
+                ("7/#188966", "春耕生产"),
+                ("8/#189001", "国际新闻"),
+            ]),
+        ),
+        (
+            "/renminribao/1958/3/05/7/",
+            page(
+                "第7版",
+                &[
+                    ("188965", "华中工学院反浪费反保守做得好"),
+                    ("188966", "春耕生产"),
+                ],
+            ),
+        ),
+        ("/renminribao/1958/3/05/8/", Response::Hang),
+        ("/renminribao/1958/4/", Response::Status(500)),
+        ("/renminribao/1959/", listing(&["12/"])),
+        (
+            "/renminribao/1959/12/",
+            listing(&["5/", "6/", "/renminribao/1959/12/05/"]),
+        ),
+        (
+            "/renminribao/1959/12/5/",
+            day(&[("4/#234881", "华中工学院下厂采集研究课题")]),
+        ),
+        (
+            "/renminribao/1959/12/5/4/",
+            page(
+                "第4版 要闻",
+                &[
+                    (
+                        "234881",
+                        "既解决了生产问题　又提高了工作能力  华中工学院下厂采集研究课题",
+                    ),
+                    ("234882", "北京大学开展科学研究"),
+                ],
+            ),
+        ),
+        (
+            "/renminribao/1959/12/6/",
+            Response::Page(
+                "<html><body><p class=\"card-text\"><a>no link</a></p></body></html>".to_string(),
+            ),
+        ),
+    ])
+}
+
+fn config(root: Url) -> Config {
+    Config {
+        root,
+        keywords: vec!["华中工学院".to_string()],
+        retries: 2,
+        retry_delay: Duration::from_millis(10),
+        control: Control::new(4),
+        client: reqwest::Client::builder()
+            .timeout(CLIENT_TIMEOUT)
+            .build()
+            .unwrap(),
+        ..Config::default()
+    }
+}
+
+#[tokio::test]
+async fn crawls_mock_archive() {
+    let archive = MockArchive::start(archive()).await;
+    let run = crawl(config(archive.root.clone()), std::future::pending());
+    let results = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+
+    let mut hits: Vec<(&str, Option<&str>)> = results
+        .successes
+        .iter()
+        .map(|hit| (hit.url.as_str(), hit.page.as_deref()))
+        .collect();
+    hits.sort();
+    let url = |path: &str| archive.root.join(path).unwrap().to_string();
+    assert_eq!(
+        hits,
+        [
+            (url("1958/3/05/7/#188965").as_str(), Some("第7版")),
+            (url("1959/12/5/4/#234881").as_str(), Some("第4版 要闻")),
+        ]
+    );
+
+    let mut failures: Vec<(String, TargetType, FailCategory, u32)> = results
+        .failures
+        .iter()
+        .map(|fail| {
+            (
+                fail.url.clone(),
+                fail.target_type,
+                fail.category,
+                fail.attempts,
+            )
+        })
+        .collect();
+    failures.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        failures,
+        [
+            (
+                url("1958/3/05/8/"),
+                TargetType::Page,
+                FailCategory::Timeout,
+                2
+            ),
+            (
+                url("1958/4/"),
+                TargetType::Month,
+                FailCategory::HttpStatus,
+                2
+            ),
+            (url("1959/12/6/"), TargetType::Day, FailCategory::Parsing, 1),
+        ]
+    );
+    assert!(results.failures.iter().all(|fail| !fail.message.is_empty()));
+
+    // 1959/12/05/ is the same day as 1959/12/5/ once canonicalized
+    assert_eq!(results.visited.duplicate_pages, 1);
+    // four articles examined plus three failed pages
+    assert_eq!(results.count, 7);
+    assert_eq!(results.articles, 4);
+}
+
+#[tokio::test]
+async fn resumes_with_the_hits_of_the_previous_run() {
+    let archive = MockArchive::start(archive()).await;
+    let run = crawl(config(archive.root.clone()), std::future::pending());
+    let first = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+
+    let directory = std::env::temp_dir().join(format!("webb-resume-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let source = FileSource::new(directory.join("visited.yaml"));
+    first.visited.store(&source);
+    let mut visited = Visited::new();
+    visited.load(&source);
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    let config = Config {
+        visited,
+        previous_successes: first.successes.clone(),
+        ..config(archive.root.clone())
+    };
+    let run = crawl(config, std::future::pending());
+    let resumed = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+    let urls = |results: &CrawlResults| {
+        let mut urls: Vec<String> = results
+            .successes
+            .iter()
+            .map(|hit| hit.url.clone())
+            .collect();
+        urls.sort();
+        urls
+    };
+    assert_eq!(urls(&resumed), urls(&first));
+    // both pages with hits were completed, only the hanging one is requested again
+    assert_eq!(resumed.visited.resumed_pages, 2);
+    assert_eq!(resumed.visited.duplicate_pages, 1);
+}
+
+#[tokio::test]
+async fn restricts_pages() {
+    let archive = MockArchive::start(archive()).await;
+    let config = Config {
+        pages: vec![4],
+        ..config(archive.root.clone())
+    };
+    let run = crawl(config, std::future::pending());
+    let results = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+    let hits: Vec<&str> = results
+        .successes
+        .iter()
+        .map(|hit| hit.url.as_str())
+        .collect();
+    assert_eq!(
+        hits,
+        [archive.root.join("1959/12/5/4/#234881").unwrap().as_str()]
+    );
+    assert!(results
+        .failures
+        .iter()
+        .all(|fail| fail.target_type != TargetType::Page));
+}
+
+#[tokio::test]
+async fn requests_each_page_once_without_retries() {
+    let archive = MockArchive::start(archive()).await;
+    let config = Config {
+        retries: 0,
+        ..config(archive.root.clone())
+    };
+    let run = crawl(config, std::future::pending());
+    let results = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+    assert_eq!(results.successes.len(), 2);
+    assert!(results.failures.iter().all(|fail| fail.attempts == 1));
+}
+
+#[tokio::test]
+async fn retries_throttled_requests_when_told() {
+    let archive = MockArchive::start(HashMap::from([
+        ("/renminribao/", listing(&["1958/", "1959/"])),
+        ("/renminribao/1958/", Response::Throttled(1)),
+    ]))
+    .await;
+    let started = std::time::Instant::now();
+    let run = crawl(config(archive.root.clone()), std::future::pending());
+    let results = tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    let attempts = |url: &str| {
+        results
+            .failures
+            .iter()
+            .find(|fail| fail.url.ends_with(url))
+            .map(|fail| (fail.attempts, fail.status))
+    };
+    assert_eq!(attempts("/1958/"), Some((2, Some(429))));
+    assert_eq!(attempts("/1959/"), Some((1, Some(404))));
+}
+
+#[tokio::test]
+async fn stops_on_request() {
+    let archive = MockArchive::start(HashMap::from([("/renminribao/", Response::Hang)])).await;
+    let config = Config {
+        client: reqwest::Client::new(),
+        ..config(archive.root.clone())
+    };
+    let stop = tokio::time::sleep(Duration::from_millis(100));
+    let run = crawl(config, stop);
+    let results = tokio::time::timeout(Duration::from_secs(5), run)
+        .await
+        .expect("crawl did not stop");
+    assert!(results.successes.is_empty());
+    assert!(results.failures.is_empty());
+}
+
+// every event emitted, in order
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Event>>>);
+
+impl Sink for Recorder {
+    fn event(&self, event: &Event) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn emits_nothing_after_stopping() {
+    let archive = MockArchive::start(HashMap::from([("/renminribao/", Response::Hang)])).await;
+    let recorder = Recorder::default();
+    let config = Config {
+        sinks: vec![Box::new(recorder.clone())],
+        ..config(archive.root.clone())
+    };
+    let stop = tokio::time::sleep(Duration::from_millis(100));
+    tokio::time::timeout(Duration::from_secs(5), crawl(config, stop))
+        .await
+        .expect("crawl did not stop");
+    // the request still in flight would have timed out and failed by now
+    tokio::time::sleep(CLIENT_TIMEOUT * 4).await;
+    let events = recorder.0.lock().unwrap();
+    assert!(matches!(events.last(), Some(Event::Finished { .. })));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, Event::Finished { .. }))
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn builder_streams_hits_within_date_range() {
+    let archive = MockArchive::start(archive()).await;
+    let mut crawler = Crawler::builder()
+        .site(archive.root.clone())
+        .keywords(["华中工学院"])
+        .date_range(DateRange::new(
+            Date::new(1959, 1, 1),
+            Date::new(1959, 12, 31),
+        ))
+        .concurrency(4)
+        .retries(1)
+        .build();
+    let mut hits = crawler.hits();
+    let results = tokio::time::timeout(Duration::from_secs(30), crawler.run())
+        .await
+        .expect("crawl did not terminate");
+
+    let streamed = hits.recv().await.expect("no hit was streamed");
+    assert_eq!(
+        streamed.url,
+        archive.root.join("1959/12/5/4/#234881").unwrap().as_str()
+    );
+    assert_eq!(results.successes.len(), 1);
+    // 1958 is never requested, so its failures do not show up
+    assert_eq!(results.failures.len(), 1);
+    assert_eq!(results.failures[0].target_type, TargetType::Day);
+}
+
+#[tokio::test]
+async fn tags_hits_with_entities() {
+    let archive = MockArchive::start(archive()).await;
+    let mut hust = Entity::new("HUST", Vec::<String>::new());
+    hust.aliases
+        .push(Alias::new("华中工学院").dates(None, Some(Date::new(1958, 12, 31))));
+    let crawler = Crawler::builder()
+        .site(archive.root.clone())
+        .entities([hust])
+        .keywords(["华中工学院"])
+        .concurrency(4)
+        .retries(1)
+        .build();
+    let results = tokio::time::timeout(Duration::from_secs(30), crawler.run())
+        .await
+        .expect("crawl did not terminate");
+
+    let mut tagged: Vec<(String, String)> = results
+        .successes
+        .iter()
+        .map(|hit| (hit.url.clone(), hit.entity.clone().unwrap()))
+        .collect();
+    tagged.sort();
+    // the alias is out of use by 1959, so the plain keyword tags that hit
+    assert_eq!(
+        tagged,
+        [
+            (
+                archive
+                    .root
+                    .join("1958/3/05/7/#188965")
+                    .unwrap()
+                    .to_string(),
+                "HUST".to_string()
+            ),
+            (
+                archive
+                    .root
+                    .join("1959/12/5/4/#234881")
+                    .unwrap()
+                    .to_string(),
+                "华中工学院".to_string()
+            ),
+        ]
+    );
+}
+
+// the rule of the example profile keeps the hits of the saved results that are about Tongji
+// and rules out the others
+#[test]
+fn example_rule_keeps_saved_hits() {
+    let profile = Profiles::load(&FileSource::new("webb.yaml"))
+        .unwrap()
+        .profile(Some("front-page"))
+        .unwrap();
+    let saved: Vec<ScannedTitle> = ["1662419929-success.yaml", "success-2003-121-1319734.yaml"]
+        .iter()
+        .flat_map(|file| {
+            serde_yaml::from_str::<Vec<ScannedTitle>>(&std::fs::read_to_string(file).unwrap())
+                .unwrap()
+        })
+        .filter(|scanned| scanned.title.contains("同济"))
+        .collect();
+    let results = Crawler::builder()
+        .entities(profile.entities.unwrap())
+        .build()
+        .rematch(saved.clone());
+    let mut excluded: Vec<&str> = results
+        .excluded
+        .iter()
+        .map(|excluded| excluded.target.title.as_str())
+        .collect();
+    excluded.sort();
+    // none of them about Tongji: a proverb, a pharmacy, a private hospital and institute, and
+    // 同 followed by 济南
+    assert_eq!(
+        excluded,
+        [
+            "“蚂蚁”如何变“大象”——记同济集团医院的发展之路",
+            "各国代表在古巴土改辩论会上同声指出  美国剥削是拉丁美洲贫困根源  拉丁美洲各国人民必须生死与共安危同济",
+            "同济堂等国药店成立协商会议  劳资合力节省开支改善经营",
+            "杏林橘井绽新枝——记石家庄同济红斑狼疮研究所",
+            "西班牙国家男子篮球队同济南部队男子篮球队在京进行友谊比赛",
+        ]
+    );
+    assert_eq!(results.successes.len(), saved.len() - excluded.len());
+}
+
+#[tokio::test]
+async fn rematches_the_corpus_offline() {
+    let archive = MockArchive::start(archive()).await;
+    let crawler = Crawler::builder()
+        .site(archive.root.clone())
+        .keywords(["华中工学院"])
+        .corpus([])
+        .concurrency(4)
+        .retries(1)
+        .build();
+    let results = tokio::time::timeout(Duration::from_secs(30), crawler.run())
+        .await
+        .expect("crawl did not terminate");
+    assert_eq!(results.corpus.len(), 4);
+    let scanned = results
+        .corpus
+        .iter()
+        .find(|scanned| scanned.url.ends_with("#234882"))
+        .unwrap();
+    assert_eq!(scanned.date, Some(Date::new(1959, 12, 5)));
+    assert_eq!(scanned.page_number, Some(4));
+
+    // another keyword, without fetching a single page
+    let rematch = |range: Option<DateRange>| {
+        let mut builder = Crawler::builder()
+            .site(archive.root.clone())
+            .keywords(["北京大学"]);
+        if let Some(range) = range {
+            builder = builder.date_range(range);
+        }
+        let mut rematcher = builder.build();
+        let events = rematcher.events();
+        (rematcher.rematch(results.corpus.clone()), events)
+    };
+    let (rematched, mut events) = rematch(None);
+    assert_eq!(rematched.count, 4);
+    // the pages of the titles are replayed, e.g. for the report to count them
+    let mut replayed = 0;
+    while let Ok(event) = events.try_recv() {
+        if let Event::LevelExpanded { articles, .. } = event {
+            replayed += articles;
+        }
+    }
+    assert_eq!(replayed, 4);
+    let hits: Vec<&str> = rematched
+        .successes
+        .iter()
+        .map(|hit| hit.url.as_str())
+        .collect();
+    assert_eq!(
+        hits,
+        [archive.root.join("1959/12/5/4/#234882").unwrap().as_str()]
+    );
+    let (within_1958, _) = rematch(Some(DateRange::new(
+        Date::new(1958, 1, 1),
+        Date::new(1958, 12, 31),
+    )));
+    assert_eq!(within_1958.count, 2);
+    assert!(within_1958.successes.is_empty());
+}
+
+#[tokio::test]
+async fn streams_events() {
+    let archive = MockArchive::start(archive()).await;
+    let mut crawler = Crawler::builder()
+        .site(archive.root.clone())
+        .keywords(["华中工学院"])
+        .concurrency(4)
+        .retries(1)
+        .build();
+    let mut events = crawler.events();
+    tokio::time::timeout(Duration::from_secs(30), crawler.run())
+        .await
+        .expect("crawl did not terminate");
+
+    let mut received = Vec::new();
+    while let Some(event) = events.recv().await {
+        received.push(event);
+    }
+    let hits = received
+        .iter()
+        .filter(|event| matches!(event, Event::HitFound(_)))
+        .count();
+    assert_eq!(hits, 2);
+    assert!(received.iter().any(|event| matches!(
+        event,
+        Event::PageFetched {
+            level: TargetType::Index,
+            ..
+        }
+    )));
+    assert!(received.iter().any(|event| matches!(
+        event,
+        Event::PageFailed(fail) if fail.target_type == TargetType::Month
+    )));
+    assert!(received.iter().any(|event| matches!(
+        event,
+        Event::LevelExpanded {
+            level: TargetType::Page,
+            articles: 2,
+            ..
+        }
+    )));
+    assert!(matches!(
+        received.last(),
+        Some(Event::Finished { successes: 2, .. })
+    ));
+}
+
+#[tokio::test]
+async fn counts_metrics() {
+    let archive = MockArchive::start(archive()).await;
+    let metrics = Metrics::new();
+    let config = Config {
+        sinks: vec![Box::new(metrics.clone())],
+        ..config(archive.root.clone())
+    };
+    let run = crawl(config, std::future::pending());
+    tokio::time::timeout(Duration::from_secs(30), run)
+        .await
+        .expect("crawl did not terminate");
+
+    let rendered = metrics.render();
+    for sample in [
+        "webb_hits_total{keyword=\"华中工学院\"} 2",
+        "webb_pages_total{level=\"page\",result=\"parsed\"} 2",
+        "webb_pages_total{level=\"page\",result=\"failed\"} 1",
+        "webb_pages_total{level=\"day\",result=\"failed\"} 1",
+        "webb_requests_total{status=\"timeout\"} 2",
+        "webb_requests_total{status=\"500\"} 2",
+        "webb_requests_total{status=\"200\"} 10",
+        "webb_retries_total 2",
+        "webb_articles_total 4",
+        "webb_jobs_running 0",
+    ] {
+        assert!(
+            rendered.lines().any(|line| line == sample),
+            "missing {} in\n{}",
+            sample,
+            rendered
+        );
+    }
+}
+
+#[tokio::test]
+async fn serves_metrics_to_bounded_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(Metrics::new().serve(listener));
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+    // a request that never ends is dropped once it grows too large
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let _ = stream.write_all(&[b'a'; 16 * 1024]).await;
+    let mut response = Vec::new();
+    let read = tokio::time::timeout(Duration::from_secs(2), stream.read_to_end(&mut response));
+    assert!(read.await.is_ok());
+    assert!(response.is_empty());
+}